@@ -120,6 +120,33 @@ impl DataType {
     }
 }
 
+impl TryFrom<u8> for DataType {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(DataType::UNDEFINED),
+            1 => Ok(DataType::F32),
+            2 => Ok(DataType::U8),
+            3 => Ok(DataType::I8),
+            4 => Ok(DataType::U16),
+            5 => Ok(DataType::I16),
+            6 => Ok(DataType::I32),
+            7 => Ok(DataType::I64),
+            8 => Ok(DataType::STRING),
+            9 => Ok(DataType::BOOL),
+            10 => Ok(DataType::FP16),
+            11 => Ok(DataType::F64),
+            12 => Ok(DataType::U32),
+            13 => Ok(DataType::U64),
+            14 => Ok(DataType::COMPLEX64),
+            15 => Ok(DataType::COMPLEX128),
+            16 => Ok(DataType::BF16),
+            _ => Err(value),
+        }
+    }
+}
+
 pub trait AsDataType {
     fn as_data_type() -> DataType;
 }
@@ -149,3 +176,82 @@ impl_as_data_type_for!(f64, F64);
 impl_as_data_type_for!(u32, U32);
 impl_as_data_type_for!(u64, U64);
 impl_as_data_type_for!(half::bf16, BF16);
+
+/// ONNX 中的数据类型名字，按数据类型的值排列。
+#[cfg(feature = "serde")]
+const ONNX_NAMES: [&str; 17] = [
+    "UNDEFINED",
+    "FLOAT",
+    "UINT8",
+    "INT8",
+    "UINT16",
+    "INT16",
+    "INT32",
+    "INT64",
+    "STRING",
+    "BOOL",
+    "FLOAT16",
+    "DOUBLE",
+    "UINT32",
+    "UINT64",
+    "COMPLEX64",
+    "COMPLEX128",
+    "BFLOAT16",
+];
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for DataType {
+    #[inline]
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(ONNX_NAMES[*self as usize])
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for DataType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = DataType;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "an ONNX data type name")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                ONNX_NAMES
+                    .iter()
+                    .position(|name| *name == v)
+                    .and_then(|i| DataType::try_from(i as u8).ok())
+                    .ok_or_else(|| E::unknown_variant(v, &ONNX_NAMES))
+            }
+        }
+
+        deserializer.deserialize_str(Visitor)
+    }
+}
+
+#[test]
+fn test_try_from_u8() {
+    for i in 0..=16 {
+        assert_eq!(DataType::try_from(i).map(|dt| dt as u8), Ok(i));
+    }
+    assert_eq!(DataType::try_from(17), Err(17));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    for (i, name) in ONNX_NAMES.iter().enumerate() {
+        let dt = DataType::try_from(i as u8).unwrap();
+        let json = serde_json::to_string(&dt).unwrap();
+        assert_eq!(json, format!("\"{name}\""));
+        assert_eq!(serde_json::from_str::<DataType>(&json).unwrap(), dt);
+    }
+    assert_eq!(serde_json::to_string(&DataType::F32).unwrap(), "\"FLOAT\"");
+
+    let err = serde_json::from_str::<DataType>("\"FLOAT32\"").unwrap_err();
+    assert!(err.to_string().contains("unknown variant `FLOAT32`"));
+    assert!(serde_json::from_str::<DataType>("1").is_err());
+}
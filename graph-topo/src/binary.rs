@@ -0,0 +1,442 @@
+use crate::{
+    container::Node, idx::try_repr, EdgeIdx, Graph, GraphTopo, Names, NodeIdx, OutputEdge,
+    TopoError,
+};
+use std::fmt;
+
+/// 文件头魔数。
+const MAGIC: [u8; 8] = *b"GRAPHTOP";
+/// 格式版本。
+const VERSION: u32 = 1;
+/// 只包含图拓扑。
+const KIND_TOPO: u8 = 0;
+/// 包含图拓扑、节点和边信息。
+const KIND_GRAPH: u8 = 1;
+/// 文件头长度：魔数、版本、类型。
+const HEADER_LEN: usize = MAGIC.len() + 4 + 1;
+/// 文件尾校验和长度。
+const CHECKSUM_LEN: usize = 8;
+
+/// 节点和边信息的二进制编解码。
+pub trait Codec: Sized {
+    /// 将信息编码到缓冲区末尾。
+    fn encode(&self, buf: &mut Vec<u8>);
+
+    /// 从 [`Codec::encode`] 写入的字节解码信息，数据无效时返回 `None`。
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+impl Codec for () {
+    #[inline]
+    fn encode(&self, _: &mut Vec<u8>) {}
+
+    #[inline]
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        bytes.is_empty().then_some(())
+    }
+}
+
+impl Codec for Vec<u8> {
+    #[inline]
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self)
+    }
+
+    #[inline]
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
+
+impl Codec for String {
+    #[inline]
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self.as_bytes())
+    }
+
+    #[inline]
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        std::str::from_utf8(bytes).ok().map(str::to_string)
+    }
+}
+
+/// 解码二进制数据时发现的错误。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DecodeError {
+    /// 文件头魔数不匹配。
+    BadMagic,
+    /// 不支持的格式版本。
+    UnsupportedVersion(u32),
+    /// 数据类型不匹配，例如用图拓扑的解码方法解码完整的图。
+    WrongKind(u8),
+    /// 数据不完整。
+    Truncated,
+    /// 校验和不匹配。
+    ChecksumMismatch,
+    /// 数据中的数量或序号超出了可表示的范围。
+    Overflow,
+    /// 数据末尾有多余的字节。
+    TrailingBytes,
+    /// 节点信息无法解码。
+    InvalidNode(NodeIdx),
+    /// 边信息无法解码。
+    InvalidEdge(EdgeIdx),
+    /// 名字不是有效的 UTF-8 字符串。
+    InvalidName,
+    /// 解码出的图不满足不变量。
+    Topology(TopoError),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadMagic => write!(f, "bad magic number"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported format version {v}"),
+            Self::WrongKind(k) => write!(f, "unexpected data kind {k}"),
+            Self::Truncated => write!(f, "data is truncated"),
+            Self::ChecksumMismatch => write!(f, "checksum mismatch"),
+            Self::Overflow => write!(f, "count or index overflows"),
+            Self::TrailingBytes => write!(f, "trailing bytes after data"),
+            Self::InvalidNode(i) => write!(f, "failed to decode node {i}"),
+            Self::InvalidEdge(i) => write!(f, "failed to decode edge {i}"),
+            Self::InvalidName => write!(f, "name is not valid UTF-8"),
+            Self::Topology(e) => write!(f, "invalid topology: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<TopoError> for DecodeError {
+    #[inline]
+    fn from(e: TopoError) -> Self {
+        Self::Topology(e)
+    }
+}
+
+impl GraphTopo {
+    /// 将图拓扑编码为二进制数据。
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = header(KIND_TOPO);
+        self.encode_body(&mut buf);
+        finish(buf)
+    }
+
+    /// 从 [`GraphTopo::encode`] 产生的二进制数据解码图拓扑。
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(bytes, KIND_TOPO)?;
+        let topo = Self::decode_body(&mut reader)?;
+        reader.finish()?;
+        topo.validate()?;
+        Ok(topo)
+    }
+
+    fn encode_body(&self, buf: &mut Vec<u8>) {
+        write_len(buf, self.global_inputs_len);
+        write_len(buf, self.global_outputs_len);
+        write_len(buf, self.nodes.len());
+        for node in &self.nodes {
+            write_len(buf, node.local_edges_len());
+            write_len(buf, node.inputs_len());
+            write_len(buf, node.outputs_len());
+        }
+        write_len(buf, self.connections.len());
+        for edge in &self.connections {
+            write_len(buf, edge.0.index());
+        }
+    }
+
+    fn decode_body(reader: &mut Reader) -> Result<Self, DecodeError> {
+        let global_inputs_len = reader.len()?;
+        let global_outputs_len = reader.len()?;
+        // 边的总数必须能用存储类型表示，否则构造边序号时会 panic
+        let mut edges_len = try_repr(global_inputs_len).ok_or(DecodeError::Overflow)?;
+        let nodes = reader.seq(24, |r| {
+            let node = Node {
+                local_edges_len: try_repr(r.len()?).ok_or(DecodeError::Overflow)?,
+                inputs_len: try_repr(r.len()?).ok_or(DecodeError::Overflow)?,
+                outputs_len: try_repr(r.len()?).ok_or(DecodeError::Overflow)?,
+            };
+            edges_len = edges_len
+                .checked_add(node.local_edges_len)
+                .and_then(|n| n.checked_add(node.outputs_len))
+                .ok_or(DecodeError::Overflow)?;
+            Ok(node)
+        })?;
+        let connections = reader.seq(8, |r| {
+            EdgeIdx::try_new(r.len()?)
+                .map(OutputEdge)
+                .ok_or(DecodeError::Overflow)
+        })?;
+        Ok(Self {
+            global_inputs_len,
+            global_outputs_len,
+            nodes,
+            connections,
+            offsets: Default::default(),
+        })
+    }
+}
+
+impl<N: Codec, E: Codec> Graph<N, E> {
+    /// 将图编码为二进制数据。
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = header(KIND_GRAPH);
+        self.topology.encode_body(&mut buf);
+        write_payloads(&mut buf, &self.nodes);
+        write_payloads(&mut buf, &self.edges);
+        match &self.names {
+            Some(names) => {
+                buf.push(1);
+                for name in names.nodes.iter().chain(&names.edges) {
+                    write_bytes(&mut buf, name.as_bytes());
+                }
+            }
+            None => buf.push(0),
+        }
+        finish(buf)
+    }
+
+    /// 从 [`Graph::encode`] 产生的二进制数据解码图。
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(bytes, KIND_GRAPH)?;
+        let topology = GraphTopo::decode_body(&mut reader)?;
+        // 先检查拓扑，保证后续的数量都是有意义的。
+        topology.validate()?;
+        let nodes = reader.seq(8, |r| r.bytes())?;
+        check_len(topology.nodes_len(), nodes.len(), |expected, actual| {
+            TopoError::NodesLen { expected, actual }
+        })?;
+        let nodes = nodes
+            .into_iter()
+            .enumerate()
+            .map(|(i, bytes)| N::decode(bytes).ok_or(DecodeError::InvalidNode(NodeIdx::new(i))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let edges = reader.seq(8, |r| r.bytes())?;
+        check_len(
+            topology.calculate_edge_len(),
+            edges.len(),
+            |expected, actual| TopoError::EdgesLen { expected, actual },
+        )?;
+        let edges = edges
+            .into_iter()
+            .enumerate()
+            .map(|(i, bytes)| E::decode(bytes).ok_or(DecodeError::InvalidEdge(EdgeIdx::new(i))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let names = match reader.take(1)?[0] {
+            0 => None,
+            1 => {
+                let mut name = || {
+                    std::str::from_utf8(reader.bytes()?)
+                        .map(str::to_string)
+                        .map_err(|_| DecodeError::InvalidName)
+                };
+                Some(Names {
+                    nodes: (0..nodes.len()).map(|_| name()).collect::<Result<_, _>>()?,
+                    edges: (0..edges.len()).map(|_| name()).collect::<Result<_, _>>()?,
+                })
+            }
+            _ => return Err(DecodeError::InvalidName),
+        };
+        reader.finish()?;
+
+        let graph = Self {
+            topology,
+            nodes,
+            edges,
+            names,
+        };
+        graph.validate()?;
+        Ok(graph)
+    }
+}
+
+/// 在解码信息之前检查数量与拓扑一致，保证信息的序号都在范围内。
+fn check_len(
+    expected: usize,
+    actual: usize,
+    err: impl FnOnce(usize, usize) -> TopoError,
+) -> Result<(), DecodeError> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(err(expected, actual).into())
+    }
+}
+
+/// FNV-1a 64 位校验和。
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn header(kind: u8) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(HEADER_LEN);
+    buf.extend_from_slice(&MAGIC);
+    buf.extend_from_slice(&VERSION.to_le_bytes());
+    buf.push(kind);
+    buf
+}
+
+fn finish(mut buf: Vec<u8>) -> Vec<u8> {
+    let sum = checksum(&buf);
+    buf.extend_from_slice(&sum.to_le_bytes());
+    buf
+}
+
+#[inline]
+fn write_len(buf: &mut Vec<u8>, len: usize) {
+    buf.extend_from_slice(&(len as u64).to_le_bytes())
+}
+
+#[inline]
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    write_len(buf, bytes.len());
+    buf.extend_from_slice(bytes)
+}
+
+fn write_payloads<T: Codec>(buf: &mut Vec<u8>, payloads: &[T]) {
+    write_len(buf, payloads.len());
+    let mut bytes = Vec::new();
+    for payload in payloads {
+        bytes.clear();
+        payload.encode(&mut bytes);
+        write_bytes(buf, &bytes);
+    }
+}
+
+/// 带边界检查的读取器，任何越界读取都返回 [`DecodeError::Truncated`]。
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    /// 检查文件头和校验和，返回只包含数据体的读取器。
+    fn new(bytes: &'a [u8], kind: u8) -> Result<Self, DecodeError> {
+        if bytes.len() < MAGIC.len() {
+            return Err(DecodeError::Truncated);
+        }
+        if bytes[..MAGIC.len()] != MAGIC {
+            return Err(DecodeError::BadMagic);
+        }
+        if bytes.len() < HEADER_LEN + CHECKSUM_LEN {
+            return Err(DecodeError::Truncated);
+        }
+        let version = u32::from_le_bytes(bytes[MAGIC.len()..][..4].try_into().unwrap());
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let (data, sum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        if checksum(data) != u64::from_le_bytes(sum.try_into().unwrap()) {
+            return Err(DecodeError::ChecksumMismatch);
+        }
+        if data[HEADER_LEN - 1] != kind {
+            return Err(DecodeError::WrongKind(data[HEADER_LEN - 1]));
+        }
+        Ok(Self(&data[HEADER_LEN..]))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.0.len() < len {
+            return Err(DecodeError::Truncated);
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn len(&mut self) -> Result<usize, DecodeError> {
+        let n = u64::from_le_bytes(self.take(8)?.try_into().unwrap());
+        usize::try_from(n).map_err(|_| DecodeError::Overflow)
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], DecodeError> {
+        let len = self.len()?;
+        self.take(len)
+    }
+
+    /// 读取一个序列，`min_size` 是每个元素至少占用的字节数，用于在分配内存前拒绝不可能的长度。
+    fn seq<T>(
+        &mut self,
+        min_size: usize,
+        mut f: impl FnMut(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<Vec<T>, DecodeError> {
+        let len = self.len()?;
+        if len > self.0.len() / min_size {
+            return Err(DecodeError::Truncated);
+        }
+        let mut ans = Vec::with_capacity(len);
+        for _ in 0..len {
+            ans.push(f(self)?);
+        }
+        Ok(ans)
+    }
+
+    fn finish(self) -> Result<(), DecodeError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(DecodeError::TrailingBytes)
+        }
+    }
+}
+
+#[test]
+fn test_binary() {
+    use crate::Builder;
+
+    let mut builder = Builder::default();
+    builder.add_global_input("a").unwrap();
+    builder
+        .add_node("A", vec!["a", "b"], vec!["c", "d"], "*0".to_string())
+        .unwrap();
+    builder
+        .add_node("B", vec!["d", "e"], vec!["z"], "*1".to_string())
+        .unwrap();
+    builder.mark_global_output("z");
+    builder.mark_global_output("c");
+    for (edge, info) in [("a", "|0"), ("b", "|1"), ("e", "|4")] {
+        builder.set_edge_info(edge, info.to_string());
+    }
+    let graph = builder.build_named().unwrap();
+
+    let bytes = graph.topology.encode();
+    assert_eq!(GraphTopo::decode(&bytes), Ok(graph.topology.clone()));
+
+    let bytes = graph.encode();
+    let decoded = Graph::<String, String>::decode(&bytes).unwrap();
+    assert_eq!(decoded.topology, graph.topology);
+    assert_eq!(decoded.nodes, graph.nodes);
+    assert_eq!(decoded.edges, graph.edges);
+    assert_eq!(decoded.names, graph.names);
+
+    for len in 0..bytes.len() {
+        assert!(Graph::<String, String>::decode(&bytes[..len]).is_err());
+    }
+    for i in 0..bytes.len() {
+        let mut bytes = bytes.clone();
+        bytes[i] ^= 0x40;
+        assert!(Graph::<String, String>::decode(&bytes).is_err());
+    }
+    assert_eq!(
+        GraphTopo::decode(&bytes),
+        Err(DecodeError::WrongKind(KIND_GRAPH))
+    );
+}
+
+#[cfg(all(feature = "compact-index", target_pointer_width = "64"))]
+#[test]
+fn test_decode_overflow() {
+    // 校验和正确，但全图输入数量超出 u32
+    let mut buf = header(KIND_TOPO);
+    for len in [5_000_000_000, 0, 1, 0, 1, 1, 1, 0] {
+        write_len(&mut buf, len);
+    }
+    assert_eq!(GraphTopo::decode(&finish(buf)), Err(DecodeError::Overflow));
+
+    // 每个数量都在范围内，但边的总数超出 u32
+    let mut buf = header(KIND_TOPO);
+    for len in [u32::MAX as usize, 0, 1, 0, 0, 1, 0] {
+        write_len(&mut buf, len);
+    }
+    assert_eq!(GraphTopo::decode(&finish(buf)), Err(DecodeError::Overflow));
+}
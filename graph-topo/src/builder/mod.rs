@@ -0,0 +1,770 @@
+mod schedule;
+
+use crate::{
+    container::{Graph, GraphTopo, Names, Node, OutputEdge},
+    EdgeIdx, NodeIdx,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    hash::Hash,
+    ops::Add,
+};
+
+pub use schedule::{Bfs, Dfs, MinLiveBytes, Ordered, Schedule, ScheduleContext};
+
+/// 图建造者。
+#[derive(Debug)]
+pub struct Builder<NodeKey, Node, EdgeKey, Edge> {
+    /// 拓扑结构记录：节点-入边-出边。
+    pub topology: HashMap<NodeKey, (Vec<EdgeKey>, Vec<EdgeKey>)>,
+    /// 全图输入边。
+    pub global_inputs: Vec<EdgeKey>,
+    /// 全图输出边。
+    pub global_outputs: Vec<EdgeKey>,
+    /// 已知的节点信息。
+    ///
+    /// 理论上说节点信息是不可推断的。
+    pub nodes: HashMap<NodeKey, Node>,
+    /// 已知的边信息。
+    ///
+    /// 大部分边信息可能是推断出来的。
+    /// 但局部边没有生产者，其信息必须在这里给出。
+    pub edges: HashMap<EdgeKey, Edge>,
+    /// 通过方法添加的边的生产者，`None` 表示全图输入。
+    ///
+    /// 只用于添加时快速查重，直接修改公开字段后可能过时，构建时仍以公开字段为准。
+    produced: HashMap<EdgeKey, Option<NodeKey>>,
+}
+
+/// 将节点和边的键转换为名字的函数。
+type Namer<'a, KN, KE> = (&'a dyn Fn(&KN) -> String, &'a dyn Fn(&KE) -> String);
+
+/// 构建图时发现的错误。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum BuildError<NodeKey, EdgeKey> {
+    /// 这些节点之间存在环，无法排出拓扑序。
+    Cycle(Vec<NodeKey>),
+    /// 边既不是全图输入，也没有节点产生它，又没有给出边信息，因此也不能作为局部边。
+    ///
+    /// 局部边的信息不会再用 `E::default()` 补全，必须用 [`Builder::set_edge_info`] 给出。
+    NoProducer(EdgeKey),
+    /// 边被多次产生（多个节点输出，或既是全图输入又是节点输出）。
+    DuplicateProducer(EdgeKey),
+    /// 缺少节点信息。
+    MissingNodeInfo(NodeKey),
+    /// 全图输出边不在图中。
+    UnknownGlobalOutput(EdgeKey),
+    /// 节点键被重复使用，且入边或出边与之前不同。
+    ConflictingNode(NodeKey),
+}
+
+impl<KN: fmt::Debug, KE: fmt::Debug> fmt::Display for BuildError<KN, KE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cycle(nodes) => write!(f, "nodes {nodes:?} form a cycle"),
+            Self::NoProducer(edge) => write!(f, "edge {edge:?} has no producer"),
+            Self::DuplicateProducer(edge) => write!(f, "edge {edge:?} is produced more than once"),
+            Self::MissingNodeInfo(node) => write!(f, "node {node:?} has no info"),
+            Self::UnknownGlobalOutput(edge) => write!(f, "global output {edge:?} is unknown"),
+            Self::ConflictingNode(node) => {
+                write!(f, "node {node:?} is redefined with a different signature")
+            }
+        }
+    }
+}
+
+impl<KN: fmt::Debug, KE: fmt::Debug> std::error::Error for BuildError<KN, KE> {}
+
+impl<KN, N, KE, E> Default for Builder<KN, N, KE, E> {
+    /// 创建一个空的建造者。
+    #[inline]
+    fn default() -> Self {
+        Self {
+            topology: Default::default(),
+            global_inputs: Default::default(),
+            global_outputs: Default::default(),
+            nodes: Default::default(),
+            edges: Default::default(),
+            produced: Default::default(),
+        }
+    }
+}
+
+impl<N, E> From<Graph<N, E>> for Builder<NodeIdx, N, EdgeIdx, E> {
+    fn from(graph: Graph<N, E>) -> Self {
+        let Graph {
+            topology,
+            nodes,
+            edges,
+            ..
+        } = graph;
+        let mut builder = Self {
+            global_inputs: topology.global_inputs().collect(),
+            produced: topology.global_inputs().map(|e| (e, None)).collect(),
+            global_outputs: topology.global_outputs().iter().map(|e| e.0).collect(),
+            nodes: nodes
+                .into_iter()
+                .enumerate()
+                .map(|(i, n)| (NodeIdx::new(i), n))
+                .collect(),
+            edges: edges
+                .into_iter()
+                .enumerate()
+                .map(|(i, e)| (EdgeIdx::new(i), e))
+                .collect(),
+            ..Default::default()
+        };
+        for (i, inputs, outputs) in &topology {
+            builder
+                .produced
+                .extend(outputs.clone().map(|e| (e, Some(i))));
+            builder
+                .topology
+                .insert(i, (inputs.iter().map(|e| e.0).collect(), outputs.collect()));
+        }
+        builder
+    }
+}
+
+impl<KN, N, KE, E> Builder<KN, N, KE, E>
+where
+    KN: Eq + Hash + Clone,
+    KE: Eq + Hash + Clone,
+{
+    /// 添加一个节点。
+    ///
+    /// 如果节点键已存在且入边和出边都相同，只更新节点信息。
+    /// 节点的出边已经被产生过，或节点键已存在但入边或出边不同时，不修改建造者并返回错误。
+    pub fn add_node(
+        &mut self,
+        key: KN,
+        inputs: Vec<KE>,
+        outputs: Vec<KE>,
+        info: N,
+    ) -> Result<(), BuildError<KN, KE>> {
+        if let Some((inputs_, outputs_)) = self.topology.get(&key) {
+            if *inputs_ != inputs || *outputs_ != outputs {
+                return Err(BuildError::ConflictingNode(key));
+            }
+            self.nodes.insert(key, info);
+            return Ok(());
+        }
+        for (i, edge) in outputs.iter().enumerate() {
+            if self.is_produced(edge) || outputs[..i].contains(edge) {
+                return Err(BuildError::DuplicateProducer(edge.clone()));
+            }
+        }
+        if outputs.iter().any(|edge| inputs.contains(edge)) {
+            return Err(BuildError::Cycle(vec![key]));
+        }
+        self.produced
+            .extend(outputs.iter().map(|edge| (edge.clone(), Some(key.clone()))));
+        self.nodes.insert(key.clone(), info);
+        self.topology.insert(key, (inputs, outputs));
+        Ok(())
+    }
+
+    /// 添加一个全图输入边。
+    ///
+    /// 边已经被产生过时返回错误。
+    pub fn add_global_input(&mut self, edge: KE) -> Result<(), BuildError<KN, KE>> {
+        if self.is_produced(&edge) {
+            return Err(BuildError::DuplicateProducer(edge));
+        }
+        self.produced.insert(edge.clone(), None);
+        self.global_inputs.push(edge);
+        Ok(())
+    }
+
+    /// 边是否已经是全图输入边或某个节点的输出边。
+    ///
+    /// 查询生产者索引，并确认记录的生产者仍然产生这条边，过时的记录视为不存在。
+    fn is_produced(&self, edge: &KE) -> bool {
+        match self.produced.get(edge) {
+            Some(Some(kn)) => self
+                .topology
+                .get(kn)
+                .is_some_and(|(_, outputs)| outputs.contains(edge)),
+            Some(None) => self.global_inputs.contains(edge),
+            None => false,
+        }
+    }
+
+    /// 将一条边标记为全图输出边。
+    ///
+    /// 边可以在之后再添加，[`Builder::build`] 会检查所有全图输出边都在图中。
+    #[inline]
+    pub fn mark_global_output(&mut self, edge: KE) {
+        self.global_outputs.push(edge)
+    }
+
+    /// 设置边信息，返回之前设置的边信息。
+    #[inline]
+    pub fn set_edge_info(&mut self, edge: KE, info: E) -> Option<E> {
+        self.edges.insert(edge, info)
+    }
+
+    /// 从全图输出边反向遍历，移除所有对全图输出没有贡献的节点，以及只与这些节点相连的边。
+    ///
+    /// 全图输入边总是保留。返回被移除的节点和边的键。
+    pub fn prune(&mut self) -> Pruned<KN, KE> {
+        let producers = self
+            .topology
+            .iter()
+            .flat_map(|(kn, (_, outputs))| outputs.iter().map(move |ke| (ke, kn)))
+            .collect::<HashMap<_, _>>();
+        let mut live_nodes = HashSet::new();
+        let mut live_edges = self
+            .global_inputs
+            .iter()
+            .chain(&self.global_outputs)
+            .collect::<HashSet<_>>();
+        let mut stack = self.global_outputs.iter().collect::<Vec<_>>();
+        while let Some(ke) = stack.pop() {
+            if let Some(kn) = producers.get(ke) {
+                if live_nodes.insert(*kn) {
+                    let (inputs, outputs) = &self.topology[*kn];
+                    live_edges.extend(inputs.iter().chain(outputs));
+                    stack.extend(inputs);
+                }
+            }
+        }
+
+        let mut pruned = Pruned {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        };
+        let mut dead_edges = HashSet::new();
+        for (kn, (inputs, outputs)) in &self.topology {
+            if !live_nodes.contains(kn) {
+                pruned.nodes.push(kn.clone());
+                for ke in inputs.iter().chain(outputs) {
+                    if !live_edges.contains(ke) && dead_edges.insert(ke) {
+                        pruned.edges.push(ke.clone());
+                    }
+                }
+            }
+        }
+        for kn in &pruned.nodes {
+            if let Some((_, outputs)) = self.topology.remove(kn) {
+                for ke in outputs {
+                    self.produced.remove(&ke);
+                }
+            }
+            self.nodes.remove(kn);
+        }
+        for ke in &pruned.edges {
+            self.edges.remove(ke);
+        }
+        pruned
+    }
+}
+
+/// 剪枝时移除的节点和边。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Pruned<NodeKey, EdgeKey> {
+    /// 被移除的节点的键。
+    pub nodes: Vec<NodeKey>,
+    /// 被移除的边的键。
+    pub edges: Vec<EdgeKey>,
+}
+
+impl<KN, N, KE, E> Builder<KN, N, KE, E>
+where
+    KN: Ord + Hash + Clone,
+    KE: Eq + Hash + Clone,
+    E: Default,
+{
+    /// 消耗生成器构造图拓扑。
+    ///
+    /// 节点按 Kahn 算法排布，多个节点同时就绪时优先排布键较小的节点，
+    /// 因此同样的建造者总是得到同样的节点和边序号。
+    /// 节点先按键排序，就绪节点再经过二叉堆排布，总复杂度是 O(V log V + E)。
+    ///
+    /// 全图输入边和节点输出边缺少边信息时使用 `E::default()`，
+    /// 但局部边必须已经用 [`Builder::set_edge_info`] 给出边信息，否则返回 [`BuildError::NoProducer`]。
+    #[inline]
+    pub fn build(self) -> Result<Graph<N, E>, BuildError<KN, KE>> {
+        self.build_with(Ordered::default())
+    }
+
+    /// 先剪去对全图输出没有贡献的节点，再消耗生成器构造图拓扑。
+    ///
+    /// 返回构建的图和被剪去的节点和边，见 [`Builder::prune`]。被剪去的节点按键排序。
+    #[allow(clippy::type_complexity)]
+    pub fn build_pruned(mut self) -> Result<(Graph<N, E>, Pruned<KN, KE>), BuildError<KN, KE>> {
+        let mut pruned = self.prune();
+        pruned.nodes.sort_unstable();
+        self.build().map(|graph| (graph, pruned))
+    }
+
+    /// 消耗生成器构造图拓扑，由调度策略决定节点的排布顺序。
+    ///
+    /// 边信息的要求与 [`Builder::build`] 相同。
+    #[inline]
+    pub fn build_with<S>(self, schedule: S) -> Result<Graph<N, E>, BuildError<KN, KE>>
+    where
+        S: Schedule<KN, KE>,
+    {
+        self.build_inner(schedule, None)
+    }
+
+    /// 消耗生成器构造图拓扑，并将节点和边的键作为名字保存在图中。
+    #[inline]
+    pub fn build_named(self) -> Result<Graph<N, E>, BuildError<KN, KE>>
+    where
+        KN: fmt::Display,
+        KE: fmt::Display,
+    {
+        self.build_named_with(Ordered::default())
+    }
+
+    /// 消耗生成器构造图拓扑，由调度策略决定节点的排布顺序，并将节点和边的键作为名字保存在图中。
+    #[inline]
+    pub fn build_named_with<S>(self, schedule: S) -> Result<Graph<N, E>, BuildError<KN, KE>>
+    where
+        KN: fmt::Display,
+        KE: fmt::Display,
+        S: Schedule<KN, KE>,
+    {
+        self.build_inner(schedule, Some((&KN::to_string, &KE::to_string)))
+    }
+
+    fn build_inner<S>(
+        mut self,
+        mut schedule: S,
+        namer: Option<Namer<KN, KE>>,
+    ) -> Result<Graph<N, E>, BuildError<KN, KE>>
+    where
+        S: Schedule<KN, KE>,
+    {
+        self.check()?;
+
+        // 按键排序的节点表，节点在表中的位置就是其内部序号。
+        let mut ctx = ScheduleContext {
+            nodes: self
+                .topology
+                .iter()
+                .map(|(kn, (inputs, outputs))| (kn, &inputs[..], &outputs[..]))
+                .collect(),
+            uses: HashMap::new(),
+        };
+        ctx.nodes.sort_unstable_by_key(|(kn, _, _)| *kn);
+        let keys = ctx.nodes.iter().map(|(kn, _, _)| *kn).collect::<Vec<_>>();
+        // 边到其生产者的映射。
+        let mut producers = HashMap::new();
+        for (i, (_, _, outputs)) in ctx.nodes.iter().enumerate() {
+            producers.extend(outputs.iter().map(|ke| (ke, i)));
+        }
+        // 每个节点未就绪的入边数、每个节点的消费者和每条边的使用次数。
+        let mut pending = vec![0usize; keys.len()];
+        let mut consumers = vec![Vec::new(); keys.len()];
+        for (i, (_, inputs, _)) in ctx.nodes.iter().enumerate() {
+            for ke in *inputs {
+                *ctx.uses.entry(ke).or_default() += 1;
+                if let Some(&producer) = producers.get(ke) {
+                    pending[i] += 1;
+                    consumers[producer].push(i);
+                }
+            }
+        }
+        for ke in &self.global_outputs {
+            *ctx.uses.entry(ke).or_default() += 1;
+        }
+        for (i, n) in pending.iter().enumerate() {
+            if *n == 0 {
+                schedule.push(i, &ctx);
+            }
+        }
+
+        // 边和序号的映射关系。
+        let mut key_to_idx = HashMap::new();
+        // 映射全图输入边。
+        let mut edges = self
+            .global_inputs
+            .iter()
+            .enumerate()
+            .map(|(i, edge)| {
+                key_to_idx.insert(edge, i);
+                self.edges.remove(edge).unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        let mut connections = Vec::with_capacity(
+            self.topology
+                .values()
+                .map(|(inputs, _)| inputs.len())
+                .sum::<usize>()
+                .add(self.global_outputs.len()),
+        );
+
+        let mut topo_nodes = Vec::with_capacity(keys.len());
+        let mut nodes = Vec::with_capacity(keys.len());
+        let mut node_names = Vec::new();
+        let policy = std::any::type_name::<S>();
+        while let Some(i) = schedule.pop(&ctx) {
+            assert!(
+                pending.get(i) == Some(&0),
+                "schedule policy `{policy}` popped node {i}, which is not ready or already popped",
+            );
+            pending[i] = usize::MAX;
+            let kn = keys[i];
+            let (inputs, outputs) = &self.topology[kn];
+            // 映射节点
+            nodes.push(self.nodes.remove(kn).unwrap());
+            if let Some((name, _)) = namer {
+                node_names.push(name(kn));
+            }
+            // 映射边，未见过的入边是这个节点的局部边
+            let edges_len = edges.len();
+            for edge in inputs {
+                if !key_to_idx.contains_key(edge) {
+                    key_to_idx.insert(edge, edges.len());
+                    edges.push(self.edges.remove(edge).unwrap());
+                }
+            }
+            topo_nodes.push(Node::new(
+                edges.len() - edges_len,
+                inputs.len(),
+                outputs.len(),
+            ));
+            for edge in outputs {
+                key_to_idx.insert(edge, edges.len());
+                edges.push(self.edges.remove(edge).unwrap_or_default());
+            }
+            // 映射连接
+            connections.extend(
+                inputs
+                    .iter()
+                    .map(|ke| OutputEdge(EdgeIdx::new(key_to_idx[ke]))),
+            );
+            // 更新后继节点
+            ctx.consume(i);
+            for &j in &consumers[i] {
+                pending[j] -= 1;
+                if pending[j] == 0 {
+                    schedule.push(j, &ctx);
+                }
+            }
+        }
+        // 有节点始终没有就绪，它们在环上或在环的下游，只报告环上的节点
+        if topo_nodes.len() < keys.len() {
+            assert!(
+                !pending.contains(&0),
+                "schedule policy `{policy}` returned `None` while nodes are ready",
+            );
+            let left = pending.iter().map(|&n| n != usize::MAX).collect::<Vec<_>>();
+            return Err(BuildError::Cycle(
+                keys.iter()
+                    .zip(on_cycle(&left, &consumers))
+                    .filter(|(_, on_cycle)| *on_cycle)
+                    .map(|(kn, _)| (*kn).clone())
+                    .collect(),
+            ));
+        }
+        // 映射全图输出边
+        connections.extend(
+            self.global_outputs
+                .iter()
+                .map(|ke| OutputEdge(EdgeIdx::new(key_to_idx[ke]))),
+        );
+        // 映射名字
+        let names = namer.map(|(_, name)| {
+            let mut edge_names = vec![String::new(); edges.len()];
+            for (ke, i) in key_to_idx {
+                edge_names[i] = name(ke);
+            }
+            Names {
+                nodes: node_names,
+                edges: edge_names,
+            }
+        });
+
+        let graph = Graph {
+            topology: GraphTopo {
+                global_inputs_len: self.global_inputs.len(),
+                global_outputs_len: self.global_outputs.len(),
+                nodes: topo_nodes,
+                connections,
+                offsets: Default::default(),
+            },
+            nodes,
+            edges,
+            names,
+        };
+        debug_assert_eq!(graph.validate(), Ok(()));
+        Ok(graph)
+    }
+
+    /// 检查建造者中的信息是否足以构建图。
+    fn check(&self) -> Result<(), BuildError<KN, KE>> {
+        // 非局部边表，即全图输入边和所有节点的输出边。
+        let mut not_local = HashSet::new();
+        for edge in &self.global_inputs {
+            if !not_local.insert(edge) {
+                return Err(BuildError::DuplicateProducer(edge.clone()));
+            }
+        }
+        for (kn, (_, outputs)) in &self.topology {
+            if !self.nodes.contains_key(kn) {
+                return Err(BuildError::MissingNodeInfo(kn.clone()));
+            }
+            for edge in outputs {
+                if !not_local.insert(edge) {
+                    return Err(BuildError::DuplicateProducer(edge.clone()));
+                }
+            }
+        }
+        // 局部边没有生产者，必须有已知的边信息。
+        let mut local = HashSet::new();
+        for (inputs, _) in self.topology.values() {
+            for edge in inputs {
+                if !not_local.contains(edge) {
+                    if !self.edges.contains_key(edge) {
+                        return Err(BuildError::NoProducer(edge.clone()));
+                    }
+                    local.insert(edge);
+                }
+            }
+        }
+        for edge in &self.global_outputs {
+            if !not_local.contains(edge) && !local.contains(edge) {
+                return Err(BuildError::UnknownGlobalOutput(edge.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 求 `alive` 中的节点构成的子图里哪些节点在环上，`successors` 是每个节点的后继。
+///
+/// 用 Kosaraju 算法求强连通分量，节点数大于 1 或有自环的分量上的节点在环上。
+fn on_cycle(alive: &[bool], successors: &[Vec<usize>]) -> Vec<bool> {
+    let n = alive.len();
+    let mut predecessors = vec![Vec::new(); n];
+    for i in (0..n).filter(|&i| alive[i]) {
+        for &j in successors[i].iter().filter(|&&j| alive[j]) {
+            predecessors[j].push(i);
+        }
+    }
+
+    // 第一遍在原图上求后序
+    let mut visited = vec![false; n];
+    let mut order = Vec::with_capacity(n);
+    for root in 0..n {
+        if !alive[root] || visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        let mut stack = vec![(root, 0)];
+        while let Some(&(i, k)) = stack.last() {
+            if let Some(&j) = successors[i].get(k) {
+                stack.last_mut().unwrap().1 += 1;
+                if alive[j] && !visited[j] {
+                    visited[j] = true;
+                    stack.push((j, 0));
+                }
+            } else {
+                order.push(i);
+                stack.pop();
+            }
+        }
+    }
+
+    // 第二遍按后序的逆序在反图上划分强连通分量
+    let mut assigned = vec![false; n];
+    let mut ans = vec![false; n];
+    for &root in order.iter().rev() {
+        if assigned[root] {
+            continue;
+        }
+        assigned[root] = true;
+        let mut component = vec![root];
+        let mut stack = vec![root];
+        while let Some(i) = stack.pop() {
+            for &j in &predecessors[i] {
+                if !assigned[j] {
+                    assigned[j] = true;
+                    component.push(j);
+                    stack.push(j);
+                }
+            }
+        }
+        if component.len() > 1 || successors[root].contains(&root) {
+            for i in component {
+                ans[i] = true;
+            }
+        }
+    }
+    ans
+}
+
+#[test]
+fn test_build_error() {
+    fn builder(
+        topology: &[(&'static str, &[&'static str], &[&'static str])],
+    ) -> Builder<&'static str, (), &'static str, ()> {
+        Builder {
+            topology: topology
+                .iter()
+                .map(|(kn, inputs, outputs)| (*kn, (inputs.to_vec(), outputs.to_vec())))
+                .collect(),
+            global_inputs: vec!["a"],
+            global_outputs: vec!["z"],
+            nodes: topology.iter().map(|(kn, _, _)| (*kn, ())).collect(),
+            ..Default::default()
+        }
+    }
+
+    assert_eq!(
+        builder(&[("A", &["a", "c"], &["b"]), ("B", &["b"], &["c", "z"])])
+            .build()
+            .unwrap_err(),
+        BuildError::Cycle(vec!["A", "B"])
+    );
+    // 环下游的节点不在环上
+    assert_eq!(
+        builder(&[
+            ("B", &["a", "c"], &["b"]),
+            ("C", &["b"], &["c"]),
+            ("D", &["c"], &["z"]),
+            ("E", &["z"], &["y"]),
+        ])
+        .build()
+        .unwrap_err(),
+        BuildError::Cycle(vec!["B", "C"])
+    );
+    assert_eq!(
+        builder(&[("A", &["a", "x"], &["z"])]).build().unwrap_err(),
+        BuildError::NoProducer("x")
+    );
+    assert_eq!(
+        builder(&[("A", &["a"], &["a", "z"])]).build().unwrap_err(),
+        BuildError::DuplicateProducer("a")
+    );
+    assert_eq!(
+        builder(&[("A", &["a"], &["b"])]).build().unwrap_err(),
+        BuildError::UnknownGlobalOutput("z")
+    );
+
+    let mut missing = builder(&[("A", &["a"], &["z"])]);
+    missing.nodes.clear();
+    assert_eq!(
+        missing.build().unwrap_err(),
+        BuildError::MissingNodeInfo("A")
+    );
+}
+
+#[test]
+#[should_panic(expected = "returned `None` while nodes are ready")]
+fn test_bad_schedule() {
+    struct Never;
+    impl<KN, KE> Schedule<KN, KE> for Never {
+        fn push(&mut self, _: usize, _: &ScheduleContext<KN, KE>) {}
+        fn pop(&mut self, _: &ScheduleContext<KN, KE>) -> Option<usize> {
+            None
+        }
+    }
+
+    let mut builder = Builder::<_, (), _, ()>::default();
+    builder.add_global_input("a").unwrap();
+    builder.add_node("A", vec!["a"], vec!["z"], ()).unwrap();
+    builder.mark_global_output("z");
+    let _ = builder.build_with(Never);
+}
+
+#[test]
+fn test_build_order() {
+    use std::collections::HashMap;
+
+    for _ in 0..8 {
+        let graph = Builder {
+            topology: HashMap::from([
+                ("D", (vec!["c"], vec!["d"])),
+                ("C", (vec!["a"], vec!["c"])),
+                ("B", (vec!["a"], vec!["b"])),
+                ("A", (vec!["b", "d"], vec!["z"])),
+            ]),
+            global_inputs: vec!["a"],
+            global_outputs: vec!["z"],
+            nodes: HashMap::from([("A", "A"), ("B", "B"), ("C", "C"), ("D", "D")]),
+            edges: HashMap::from([("a", "a"), ("b", "b"), ("c", "c"), ("d", "d")]),
+            ..Default::default()
+        }
+        .build()
+        .unwrap();
+        assert_eq!(graph.nodes, ["B", "C", "D", "A"]);
+        assert_eq!(graph.edges, ["a", "b", "c", "d", ""]);
+    }
+}
+
+#[test]
+fn test_add_error() {
+    let mut builder = Builder::<_, (), _, ()>::default();
+    builder.add_global_input("a").unwrap();
+    builder.add_node("A", vec!["a"], vec!["b"], ()).unwrap();
+    builder.add_node("A", vec!["a"], vec!["b"], ()).unwrap();
+    assert_eq!(
+        builder.add_global_input("a"),
+        Err(BuildError::DuplicateProducer("a"))
+    );
+    assert_eq!(
+        builder.add_node("A", vec!["a"], vec!["c"], ()),
+        Err(BuildError::ConflictingNode("A"))
+    );
+    assert_eq!(
+        builder.add_node("B", vec!["a"], vec!["c", "b"], ()),
+        Err(BuildError::DuplicateProducer("b"))
+    );
+    assert_eq!(
+        builder.add_node("B", vec!["c"], vec!["c"], ()),
+        Err(BuildError::Cycle(vec!["B"]))
+    );
+    assert_eq!(builder.topology.len(), 1);
+
+    // 直接修改公开字段后，检查仍然基于当前的内容
+    builder.topology.remove("A");
+    builder.add_node("B", vec!["a"], vec!["b"], ()).unwrap();
+}
+
+#[test]
+fn test_build_named() {
+    let mut builder = Builder::<_, (), _, ()>::default();
+    builder.add_global_input("a").unwrap();
+    builder.add_node("B", vec!["a"], vec!["b"], ()).unwrap();
+    builder
+        .add_node("A", vec!["b", "w"], vec!["z"], ())
+        .unwrap();
+    builder.set_edge_info("w", ());
+    builder.mark_global_output("z");
+
+    let graph = builder.build_named().unwrap();
+    let names = graph.names.as_ref().unwrap();
+    assert_eq!(names.nodes, ["B", "A"]);
+    assert_eq!(names.edges, ["a", "b", "w", "z"]);
+    assert_eq!(graph.node_name(NodeIdx::new(1)), Some("A"));
+    assert_eq!(graph.edge_name(EdgeIdx::new(4)), None);
+}
+
+#[test]
+fn test_build_pruned() {
+    let mut builder = Builder::<_, _, _, ()>::default();
+    builder.add_global_input("a").unwrap();
+    builder
+        .add_node("A", vec!["a"], vec!["b", "c"], "A")
+        .unwrap();
+    builder
+        .add_node("B", vec!["c", "w"], vec!["d"], "B")
+        .unwrap();
+    builder.add_node("C", vec!["d"], vec!["e"], "C").unwrap();
+    builder.add_node("D", vec!["b"], vec!["z"], "D").unwrap();
+    builder.mark_global_output("z");
+    // 死节点没有信息也不影响构建
+    builder.nodes.remove("C");
+
+    let (graph, mut pruned) = builder.build_pruned().unwrap();
+    pruned.edges.sort_unstable();
+    assert_eq!(graph.nodes, ["A", "D"]);
+    assert_eq!(pruned.nodes, ["B", "C"]);
+    assert_eq!(pruned.edges, ["d", "e", "w"]);
+}
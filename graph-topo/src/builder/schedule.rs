@@ -0,0 +1,211 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, VecDeque},
+    hash::Hash,
+};
+
+/// 调度策略。
+///
+/// 建造者按 Kahn 算法排布节点，每当有节点就绪时通知策略，
+/// 并由策略决定下一个排布哪个就绪节点。
+/// 节点用其在所有节点键排序后的位置表示，因此序号越小的节点键越小。
+pub trait Schedule<NodeKey, EdgeKey> {
+    /// 节点 `node` 就绪。
+    fn push(&mut self, node: usize, ctx: &ScheduleContext<NodeKey, EdgeKey>);
+
+    /// 取出下一个要排布的节点。
+    ///
+    /// 只能返回已经就绪且尚未取出的节点，仅当没有就绪节点时返回 `None`。
+    /// 违反这个约定时构建会 panic。
+    fn pop(&mut self, ctx: &ScheduleContext<NodeKey, EdgeKey>) -> Option<usize>;
+}
+
+/// 调度过程中可供策略查询的信息。
+pub struct ScheduleContext<'a, NodeKey, EdgeKey> {
+    /// 按键排序的节点：键-入边-出边。
+    pub(super) nodes: Vec<(&'a NodeKey, &'a [EdgeKey], &'a [EdgeKey])>,
+    /// 每条边剩余的使用次数。
+    pub(super) uses: HashMap<&'a EdgeKey, usize>,
+}
+
+impl<'a, KN, KE: Eq + Hash> ScheduleContext<'a, KN, KE> {
+    /// 节点数量。
+    #[inline]
+    pub fn nodes_len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// 节点的键。
+    #[inline]
+    pub fn key(&self, node: usize) -> &'a KN {
+        self.nodes[node].0
+    }
+
+    /// 节点的入边。
+    #[inline]
+    pub fn inputs(&self, node: usize) -> &'a [KE] {
+        self.nodes[node].1
+    }
+
+    /// 节点的出边。
+    #[inline]
+    pub fn outputs(&self, node: usize) -> &'a [KE] {
+        self.nodes[node].2
+    }
+
+    /// 边还有多少次使用尚未排布。
+    ///
+    /// 每次作为节点入边算一次使用，作为全图输出算一次永远不会排布的使用。
+    #[inline]
+    pub fn remaining_uses(&self, edge: &KE) -> usize {
+        self.uses.get(edge).copied().unwrap_or(0)
+    }
+
+    /// 排布节点 `node` 后，更新其入边的剩余使用次数。
+    pub(super) fn consume(&mut self, node: usize) {
+        for edge in self.nodes[node].1 {
+            if let Some(n) = self.uses.get_mut(edge) {
+                *n -= 1;
+            }
+        }
+    }
+}
+
+/// 优先排布键最小的就绪节点。
+///
+/// 这是 [`Builder::build`](crate::Builder::build) 使用的默认策略。
+/// 如果节点的键本身就是一种拓扑序，构建结果的节点顺序与之相同。
+/// 就绪节点保存在二叉堆中，每次出入堆 O(log V)，因此排布的总复杂度是 O(V log V + E)。
+#[derive(Clone, Default, Debug)]
+pub struct Ordered(BinaryHeap<Reverse<usize>>);
+
+impl<KN, KE> Schedule<KN, KE> for Ordered {
+    #[inline]
+    fn push(&mut self, node: usize, _: &ScheduleContext<KN, KE>) {
+        self.0.push(Reverse(node))
+    }
+
+    #[inline]
+    fn pop(&mut self, _: &ScheduleContext<KN, KE>) -> Option<usize> {
+        self.0.pop().map(|Reverse(i)| i)
+    }
+}
+
+/// 广度优先，按就绪的先后排布节点。
+#[derive(Clone, Default, Debug)]
+pub struct Bfs(VecDeque<usize>);
+
+impl<KN, KE> Schedule<KN, KE> for Bfs {
+    #[inline]
+    fn push(&mut self, node: usize, _: &ScheduleContext<KN, KE>) {
+        self.0.push_back(node)
+    }
+
+    #[inline]
+    fn pop(&mut self, _: &ScheduleContext<KN, KE>) -> Option<usize> {
+        self.0.pop_front()
+    }
+}
+
+/// 深度优先，优先排布最后就绪的节点，即完成一个分支后再开始另一个分支。
+#[derive(Clone, Default, Debug)]
+pub struct Dfs(Vec<usize>);
+
+impl<KN, KE> Schedule<KN, KE> for Dfs {
+    #[inline]
+    fn push(&mut self, node: usize, _: &ScheduleContext<KN, KE>) {
+        self.0.push(node)
+    }
+
+    #[inline]
+    fn pop(&mut self, _: &ScheduleContext<KN, KE>) -> Option<usize> {
+        self.0.pop()
+    }
+}
+
+/// 贪心地选择使存活字节数增长最少的就绪节点。
+///
+/// 排布一个节点会分配其所有出边，并释放最后一次使用发生在这个节点的入边。
+/// 全图输出边永远不会被释放。增长相同时优先排布键较小的节点。
+#[derive(Clone, Debug)]
+pub struct MinLiveBytes<F> {
+    size: F,
+    ready: Vec<usize>,
+}
+
+impl<F> MinLiveBytes<F> {
+    /// 用计算边字节数的函数创建策略。
+    #[inline]
+    pub fn new(size: F) -> Self {
+        Self {
+            size,
+            ready: Vec::new(),
+        }
+    }
+}
+
+impl<KN, KE, F> Schedule<KN, KE> for MinLiveBytes<F>
+where
+    KE: Eq + Hash,
+    F: FnMut(&KE) -> usize,
+{
+    #[inline]
+    fn push(&mut self, node: usize, _: &ScheduleContext<KN, KE>) {
+        self.ready.push(node)
+    }
+
+    fn pop(&mut self, ctx: &ScheduleContext<KN, KE>) -> Option<usize> {
+        let (pos, _) = self
+            .ready
+            .iter()
+            .enumerate()
+            .map(|(pos, &node)| {
+                let inputs = ctx.inputs(node);
+                let allocated = ctx.outputs(node).iter().map(&mut self.size).sum::<usize>();
+                let freed = inputs
+                    .iter()
+                    .enumerate()
+                    // 每条边只计算第一次出现
+                    .filter(|(i, edge)| !inputs[..*i].contains(edge))
+                    .filter(|(_, edge)| {
+                        ctx.remaining_uses(edge) == inputs.iter().filter(|e| e == edge).count()
+                    })
+                    .map(|(_, edge)| (self.size)(edge))
+                    .sum::<usize>();
+                (pos, (allocated as isize - freed as isize, node))
+            })
+            .min_by_key(|(_, key)| *key)?;
+        Some(self.ready.swap_remove(pos))
+    }
+}
+
+#[test]
+fn test_schedule() {
+    use crate::Builder;
+
+    fn build(schedule: impl Schedule<&'static str, &'static str>) -> Vec<&'static str> {
+        let mut builder = Builder::<_, _, _, ()>::default();
+        builder.add_global_input("a").unwrap();
+        builder.add_node("A", vec!["a"], vec!["b"], "A").unwrap();
+        builder.add_node("B", vec!["b"], vec!["c"], "B").unwrap();
+        builder.add_node("C", vec!["a"], vec!["d"], "C").unwrap();
+        builder.add_node("D", vec!["d"], vec!["e"], "D").unwrap();
+        builder
+            .add_node("E", vec!["c", "e"], vec!["z"], "E")
+            .unwrap();
+        builder.mark_global_output("z");
+        builder.build_with(schedule).unwrap().nodes
+    }
+
+    assert_eq!(build(Ordered::default()), ["A", "B", "C", "D", "E"]);
+    assert_eq!(build(Bfs::default()), ["A", "C", "B", "D", "E"]);
+    assert_eq!(build(Dfs::default()), ["C", "D", "A", "B", "E"]);
+    assert_eq!(
+        build(MinLiveBytes::new(|e: &&str| if *e == "b" {
+            100
+        } else {
+            1
+        })),
+        ["C", "D", "A", "B", "E"]
+    );
+}
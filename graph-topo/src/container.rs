@@ -1,7 +1,22 @@
-﻿use std::ops::{Add, Range};
+use crate::{
+    idx::{repr, Repr},
+    Builder, EdgeIdx, EdgeRange, NodeIdx,
+};
+use std::{
+    fmt,
+    ops::{Add, Index, IndexMut},
+    sync::OnceLock,
+};
 
 /// 图拓扑结构。
-#[derive(Clone, Default, Debug)]
+///
+/// 反序列化时会检查拓扑的不变量。
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "crate::serialize::RawGraphTopo")
+)]
 pub struct GraphTopo {
     /// 全图输入边的数量。
     pub(super) global_inputs_len: usize,
@@ -16,12 +31,55 @@ pub struct GraphTopo {
     /// 该集合的排列顺序是由节点集合决定的。
     /// 首先是每个节点的输入边，最后是全图的输出边。
     ///
-    /// 即：`connections.len() == nodes.sum(inputs_len) + global_outputs_lenws`。
+    /// 即：`connections.len() == nodes.sum(inputs_len) + global_outputs_len`。
     pub(super) connections: Vec<OutputEdge>,
+    /// 缓存的节点偏移量，用于随机访问节点。
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(super) offsets: Offsets,
+}
+
+/// 每个节点第一个入边的连接序号和第一条局部边的边序号，最后一项是总数。
+///
+/// 第一次随机访问时计算。缓存不参与比较，修改拓扑后需要清空。
+#[derive(Clone, Default)]
+pub(super) struct Offsets(OnceLock<Box<[(Repr, Repr)]>>);
+
+impl PartialEq for Offsets {
+    #[inline]
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Offsets {}
+
+impl fmt::Debug for Offsets {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("..")
+    }
+}
+
+/// 边的来源。
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum EdgeKind {
+    /// 全图输入边。
+    GlobalInput,
+    /// 节点的局部边。
+    Local(NodeIdx),
+    /// 节点的出边。
+    Output(NodeIdx),
 }
 
 /// 用于保存构建结果的数据结构，对节点和边重新排序。
+///
+/// 反序列化时会检查拓扑的不变量，以及节点信息、边信息和名字表与拓扑一致。
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "crate::serialize::RawGraph<Node, Edge>")
+)]
 pub struct Graph<Node, Edge> {
     /// 节点和边的拓扑结构。
     pub topology: GraphTopo,
@@ -29,43 +87,157 @@ pub struct Graph<Node, Edge> {
     pub nodes: Vec<Node>,
     /// 所有边的信息。
     pub edges: Vec<Edge>,
+    /// 节点和边的名字，用于诊断信息。
+    pub names: Option<Names>,
+}
+
+/// 节点和边的名字表。
+///
+/// 名字通常来自构建图时使用的键，为空表示没有名字。
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Names {
+    /// 每个节点的名字。
+    pub nodes: Vec<String>,
+    /// 每条边的名字。
+    pub edges: Vec<String>,
 }
 
 /// 节点结构。
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(super) struct Node {
-    pub(super) local_edges_len: usize,
-    pub(super) inputs_len: usize,
-    pub(super) outputs_len: usize,
+    pub(super) local_edges_len: Repr,
+    pub(super) inputs_len: Repr,
+    pub(super) outputs_len: Repr,
+}
+
+impl Node {
+    #[inline]
+    pub(super) const fn new(local_edges_len: usize, inputs_len: usize, outputs_len: usize) -> Self {
+        Self {
+            local_edges_len: repr(local_edges_len),
+            inputs_len: repr(inputs_len),
+            outputs_len: repr(outputs_len),
+        }
+    }
+
+    /// 节点的局部边数量。
+    #[inline]
+    pub(super) const fn local_edges_len(&self) -> usize {
+        self.local_edges_len as _
+    }
+
+    /// 节点的入边数量。
+    #[inline]
+    pub(super) const fn inputs_len(&self) -> usize {
+        self.inputs_len as _
+    }
+
+    /// 节点的出边数量。
+    #[inline]
+    pub(super) const fn outputs_len(&self) -> usize {
+        self.outputs_len as _
+    }
 }
 
 /// 作为节点输入的边序号。
-#[derive(Clone, PartialEq, Eq, Debug)]
-pub struct OutputEdge(pub usize);
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct OutputEdge(pub EdgeIdx);
+
+impl<N, E> Graph<N, E> {
+    /// 将图分解为以节点和边序号为键的建造者，以便编辑后重新构建。
+    ///
+    /// 对于由建造者构建的图，直接重新构建会得到相同的图。名字表不会保留。
+    #[inline]
+    pub fn into_builder(self) -> Builder<NodeIdx, N, EdgeIdx, E> {
+        Builder::from(self)
+    }
+
+    /// 节点的名字。
+    pub fn node_name(&self, i: NodeIdx) -> Option<&str> {
+        self.names
+            .as_ref()
+            .and_then(|names| names.nodes.get(i.index()))
+            .map(String::as_str)
+            .filter(|name| !name.is_empty())
+    }
+
+    /// 边的名字。
+    pub fn edge_name(&self, i: EdgeIdx) -> Option<&str> {
+        self.names
+            .as_ref()
+            .and_then(|names| names.edges.get(i.index()))
+            .map(String::as_str)
+            .filter(|name| !name.is_empty())
+    }
+}
+
+impl<N, E> Index<NodeIdx> for Graph<N, E> {
+    type Output = N;
+
+    #[inline]
+    fn index(&self, i: NodeIdx) -> &Self::Output {
+        &self.nodes[i.index()]
+    }
+}
+
+impl<N, E> IndexMut<NodeIdx> for Graph<N, E> {
+    #[inline]
+    fn index_mut(&mut self, i: NodeIdx) -> &mut Self::Output {
+        &mut self.nodes[i.index()]
+    }
+}
+
+impl<N, E> Index<EdgeIdx> for Graph<N, E> {
+    type Output = E;
+
+    #[inline]
+    fn index(&self, i: EdgeIdx) -> &Self::Output {
+        &self.edges[i.index()]
+    }
+}
+
+impl<N, E> IndexMut<EdgeIdx> for Graph<N, E> {
+    #[inline]
+    fn index_mut(&mut self, i: EdgeIdx) -> &mut Self::Output {
+        &mut self.edges[i.index()]
+    }
+}
 
 /// 用于遍历图拓扑的结构。
+///
+/// 可以用 [`Iterator::rev`] 逆拓扑序遍历。
 pub struct Iter<'a> {
     inner: &'a GraphTopo,
     i: usize,
     pass_connections: usize,
     pass_edges: usize,
+    /// 反向遍历的位置：节点、连接和边的末尾，第一次反向遍历时初始化。
+    back: Option<(usize, usize, usize)>,
 }
 
 impl GraphTopo {
     /// 遍历迭代器。
     #[inline]
-    pub const fn traverse(&self) -> Iter {
+    pub const fn traverse(&self) -> Iter<'_> {
         Iter {
             inner: self,
             i: 0,
             pass_connections: 0,
             pass_edges: self.global_inputs_len,
+            back: None,
         }
     }
 
     /// 遍历迭代器。
     #[inline]
-    pub const fn iter(&self) -> Iter {
+    pub const fn iter(&self) -> Iter<'_> {
         self.traverse()
     }
 
@@ -78,9 +250,9 @@ impl GraphTopo {
     pub fn calculate_edge_len(&self) -> usize {
         self.nodes
             .iter()
-            .map(|node| node.local_edges_len + node.outputs_len)
+            .map(|node| node.local_edges_len() + node.outputs_len())
             .sum::<usize>()
-            .add(self.global_outputs_len)
+            .add(self.global_inputs_len)
     }
 
     /// 全图输入边的数量。
@@ -94,18 +266,69 @@ impl GraphTopo {
     }
 
     /// 全图输入边集。
-    pub const fn global_inputs(&self) -> Range<usize> {
-        0..self.global_inputs_len
+    pub const fn global_inputs(&self) -> EdgeRange {
+        EdgeRange::new(0..self.global_inputs_len)
     }
 
     /// 全图输出边集。
     pub fn global_outputs(&self) -> &[OutputEdge] {
-        &self.connections[..self.global_outputs_len]
+        &self.connections[self.connections.len() - self.global_outputs_len..]
+    }
+
+    /// 节点的入边和出边，与遍历得到的结果相同。
+    ///
+    /// 第一次调用时计算并缓存所有节点的偏移量，之后的调用为 O(1)。
+    pub fn node(&self, i: NodeIdx) -> (&[OutputEdge], EdgeRange) {
+        let node = &self.nodes[i.index()];
+        let (first_input, first_edge) = self.offsets()[i.index()];
+        let first_input: usize = first_input as _;
+        let first_edge: usize = first_edge as _;
+        let first_output = first_edge + node.local_edges_len();
+        (
+            &self.connections[first_input..][..node.inputs_len()],
+            EdgeRange::new(first_output..first_output + node.outputs_len()),
+        )
+    }
+
+    /// 边的来源，边不存在时返回 `None`。
+    pub fn edge_kind(&self, e: EdgeIdx) -> Option<EdgeKind> {
+        let e = e.index();
+        if e < self.global_inputs_len {
+            return Some(EdgeKind::GlobalInput);
+        }
+        let offsets = self.offsets();
+        if e >= offsets[self.nodes.len()].1 as _ {
+            return None;
+        }
+        let i = offsets.partition_point(|&(_, first)| e >= first as _) - 1;
+        let first_edge: usize = offsets[i].1 as _;
+        let ans = if e < first_edge + self.nodes[i].local_edges_len() {
+            EdgeKind::Local(NodeIdx::new(i))
+        } else {
+            EdgeKind::Output(NodeIdx::new(i))
+        };
+        Some(ans)
+    }
+
+    /// 获取或计算节点偏移量。
+    fn offsets(&self) -> &[(Repr, Repr)] {
+        self.offsets.0.get_or_init(|| {
+            let mut connections = 0;
+            let mut edges = self.global_inputs_len;
+            let mut ans = Vec::with_capacity(self.nodes.len() + 1);
+            for node in &self.nodes {
+                ans.push((repr(connections), repr(edges)));
+                connections += node.inputs_len();
+                edges += node.local_edges_len() + node.outputs_len();
+            }
+            ans.push((repr(connections), repr(edges)));
+            ans.into()
+        })
     }
 }
 
 impl<'a> IntoIterator for &'a GraphTopo {
-    type Item = (usize, &'a [OutputEdge], Range<usize>);
+    type Item = (NodeIdx, &'a [OutputEdge], EdgeRange);
 
     type IntoIter = Iter<'a>;
 
@@ -116,23 +339,131 @@ impl<'a> IntoIterator for &'a GraphTopo {
 }
 
 impl<'a> Iterator for Iter<'a> {
-    type Item = (usize, &'a [OutputEdge], Range<usize>);
+    type Item = (NodeIdx, &'a [OutputEdge], EdgeRange);
 
     fn next(&mut self) -> Option<Self::Item> {
         let i = self.i;
+        if self.back.is_some_and(|(end, _, _)| i >= end) {
+            return None;
+        }
         let node = self.inner.nodes.get(i)?;
         self.i += 1;
 
         let first_input = self.pass_connections;
-        self.pass_connections += node.inputs_len;
-        self.pass_edges += node.local_edges_len;
+        self.pass_connections += node.inputs_len();
+        self.pass_edges += node.local_edges_len();
         let first_edge = self.pass_edges;
-        self.pass_edges += node.outputs_len;
+        self.pass_edges += node.outputs_len();
 
         Some((
-            i,
+            NodeIdx::new(i),
             &self.inner.connections[first_input..self.pass_connections],
-            first_edge..self.pass_edges,
+            EdgeRange::new(first_edge..self.pass_edges),
         ))
     }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let end = self.back.map_or(self.inner.nodes.len(), |(end, _, _)| end);
+        let len = end.saturating_sub(self.i);
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let inner = self.inner;
+        let (end, connections, edges) = self.back.get_or_insert_with(|| {
+            let (connections, edges) = inner.offsets()[inner.nodes.len()];
+            (inner.nodes.len(), connections as _, edges as _)
+        });
+        if *end <= self.i {
+            return None;
+        }
+        *end -= 1;
+        let node = &inner.nodes[*end];
+
+        let last_input = *connections;
+        *connections -= node.inputs_len();
+        let last_edge = *edges;
+        *edges -= node.outputs_len();
+        let first_edge = *edges;
+        *edges -= node.local_edges_len();
+
+        Some((
+            NodeIdx::new(*end),
+            &inner.connections[*connections..last_input],
+            EdgeRange::new(first_edge..last_edge),
+        ))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+#[test]
+fn test_traverse_rev() {
+    let mut builder = Builder::<_, _, _, ()>::default();
+    builder.add_global_input("a").unwrap();
+    builder
+        .add_node("A", vec!["a", "b"], vec!["c", "d"], ())
+        .unwrap();
+    builder
+        .add_node("B", vec!["d", "e"], vec!["f"], ())
+        .unwrap();
+    builder
+        .add_node("C", vec!["f", "c"], vec!["z"], ())
+        .unwrap();
+    builder.mark_global_output("z");
+    builder.set_edge_info("b", ());
+    builder.set_edge_info("e", ());
+    let topo = builder.build().unwrap().topology;
+
+    let forward = topo.traverse().collect::<Vec<_>>();
+    let mut backward = topo.traverse().rev().collect::<Vec<_>>();
+    backward.reverse();
+    assert_eq!(backward, forward);
+
+    let mut iter = topo.traverse();
+    assert_eq!(iter.len(), 3);
+    assert_eq!(iter.next_back(), Some(forward[2].clone()));
+    assert_eq!(iter.next(), Some(forward[0].clone()));
+    assert_eq!(iter.len(), 1);
+    assert_eq!(iter.next_back(), Some(forward[1].clone()));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+}
+
+#[test]
+fn test_node() {
+    let mut builder = Builder::<_, _, _, ()>::default();
+    builder.add_global_input("a").unwrap();
+    builder
+        .add_node("A", vec!["a", "b"], vec!["c", "d"], ())
+        .unwrap();
+    builder
+        .add_node("B", vec!["d", "e"], vec!["f"], ())
+        .unwrap();
+    builder.mark_global_output("f");
+    builder.set_edge_info("b", ());
+    builder.set_edge_info("e", ());
+    let topo = builder.build().unwrap().topology;
+
+    for (i, inputs, outputs) in &topo {
+        assert_eq!(topo.node(i), (inputs, outputs));
+    }
+    let kinds = (0..7)
+        .map(|e| topo.edge_kind(EdgeIdx::new(e)))
+        .collect::<Vec<_>>();
+    let (a, b) = (NodeIdx::new(0), NodeIdx::new(1));
+    assert_eq!(
+        kinds,
+        [
+            Some(EdgeKind::GlobalInput),
+            Some(EdgeKind::Local(a)),
+            Some(EdgeKind::Output(a)),
+            Some(EdgeKind::Output(a)),
+            Some(EdgeKind::Local(b)),
+            Some(EdgeKind::Output(b)),
+            None,
+        ]
+    );
 }
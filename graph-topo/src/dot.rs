@@ -0,0 +1,154 @@
+use crate::{EdgeIdx, Graph, NodeIdx};
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+};
+
+/// 边在 DOT 图中的起点。
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Source {
+    /// 第 k 个全图输入。
+    Input(usize),
+    /// 局部边。
+    Local(EdgeIdx),
+    /// 节点的输出。
+    Node(NodeIdx),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Input(k) => write!(f, "in{k}"),
+            Self::Local(e) => write!(f, "e{e}"),
+            Self::Node(i) => write!(f, "n{i}"),
+        }
+    }
+}
+
+impl<N, E> Graph<N, E> {
+    /// 生成 Graphviz DOT 格式的图描述。
+    ///
+    /// 节点和边的标签由 `node_label` 和 `edge_label` 生成。
+    /// 全图输入和输出绘制为单独的形状，局部边以高亮显示；
+    /// 两个节点之间有多条边时，在边的末端标出入边的位置。
+    pub fn to_dot(
+        &self,
+        node_label: impl Fn(&N) -> String,
+        edge_label: impl Fn(&E) -> String,
+    ) -> String {
+        let topo = &self.topology;
+        let mut dot = String::from("digraph {\n");
+        // 按边序号的顺序记录每条边的起点。
+        let mut sources = Vec::with_capacity(self.edges.len());
+        for k in 0..topo.global_inputs_len() {
+            sources.push(Source::Input(k));
+            writeln!(
+                dot,
+                "    in{k} [shape=invhouse, style=filled, fillcolor=lightblue, label=\"input {k}\"];"
+            )
+            .unwrap();
+        }
+        for (i, _, outputs) in topo {
+            for e in (sources.len()..outputs.start().index()).map(EdgeIdx::new) {
+                sources.push(Source::Local(e));
+                writeln!(
+                    dot,
+                    "    e{e} [shape=note, style=filled, fillcolor=lightyellow, label=\"local {e}\"];"
+                )
+                .unwrap();
+            }
+            sources.extend(outputs.map(|_| Source::Node(i)));
+            writeln!(
+                dot,
+                "    n{i} [shape=box, label=\"{}\"];",
+                escape(&node_label(&self.nodes[i.index()]))
+            )
+            .unwrap();
+        }
+        for k in 0..topo.global_outputs_len() {
+            writeln!(
+                dot,
+                "    out{k} [shape=house, style=filled, fillcolor=lightpink, label=\"output {k}\"];"
+            )
+            .unwrap();
+        }
+
+        let mut arcs = Vec::new();
+        for (i, inputs, _) in topo {
+            for (slot, input) in inputs.iter().enumerate() {
+                arcs.push((input.0, format!("n{i}"), slot));
+            }
+        }
+        for (k, output) in topo.global_outputs().iter().enumerate() {
+            arcs.push((output.0, format!("out{k}"), 0));
+        }
+        let mut multiplicity = HashMap::<(Source, &str), usize>::new();
+        for (e, target, _) in &arcs {
+            *multiplicity
+                .entry((sources[e.index()], target))
+                .or_default() += 1;
+        }
+        for (e, target, slot) in &arcs {
+            let source = sources[e.index()];
+            write!(
+                dot,
+                "    {source} -> {target} [label=\"{}\"",
+                escape(&edge_label(&self.edges[e.index()]))
+            )
+            .unwrap();
+            if matches!(source, Source::Local(_)) {
+                dot.push_str(", color=orange, fontcolor=orange, penwidth=2");
+            }
+            if multiplicity[&(source, target.as_str())] > 1 {
+                write!(dot, ", headlabel=\"{slot}\"").unwrap();
+            }
+            dot.push_str("];\n");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// 转义 DOT 字符串中的特殊字符。
+fn escape(s: &str) -> String {
+    let mut ans = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => ans.push_str("\\\""),
+            '\\' => ans.push_str("\\\\"),
+            '\n' => ans.push_str("\\n"),
+            c => ans.push(c),
+        }
+    }
+    ans
+}
+
+#[test]
+fn test_to_dot() {
+    use crate::Builder;
+
+    let mut builder = Builder::default();
+    builder.add_global_input("a").unwrap();
+    builder
+        .add_node("A", vec!["a", "b"], vec!["c"], "Add")
+        .unwrap();
+    builder
+        .add_node("B", vec!["c", "c"], vec!["z"], "Mul \"x\"")
+        .unwrap();
+    builder.mark_global_output("z");
+    for (edge, info) in [("a", "a"), ("b", "b"), ("c", "c"), ("z", "z")] {
+        builder.set_edge_info(edge, info);
+    }
+    let graph = builder.build().unwrap();
+
+    let dot = graph.to_dot(|n| n.to_string(), |e| e.to_string());
+    assert!(dot.starts_with("digraph {\n"));
+    assert!(dot.contains("in0 [shape=invhouse"));
+    assert!(dot.contains("out0 [shape=house"));
+    assert!(dot.contains("n1 [shape=box, label=\"Mul \\\"x\\\"\"];"));
+    assert!(dot.contains("in0 -> n0 [label=\"a\"];"));
+    assert!(dot.contains("e1 -> n0 [label=\"b\", color=orange"));
+    assert!(dot.contains("n0 -> n1 [label=\"c\", headlabel=\"0\"];"));
+    assert!(dot.contains("n0 -> n1 [label=\"c\", headlabel=\"1\"];"));
+    assert!(dot.contains("n1 -> out0 [label=\"z\"];"));
+}
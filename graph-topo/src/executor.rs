@@ -0,0 +1,296 @@
+use crate::{Graph, NodeIdx};
+use std::{
+    fmt,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, PoisonError, RwLock,
+    },
+    thread,
+};
+
+/// 取消执行的令牌，克隆出的令牌共享同一个状态。
+#[derive(Clone, Default, Debug)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// 请求取消执行，正在执行的节点会执行完，之后不再开始新的节点。
+    #[inline]
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+
+    /// 是否已请求取消。
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// 执行图时发生的错误。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ExecuteError<Err> {
+    /// 节点执行失败，这是第一个失败的节点。
+    Failed {
+        /// 失败的节点。
+        node: NodeIdx,
+        /// 节点返回的错误。
+        error: Err,
+    },
+    /// 执行被取消，部分节点没有执行。
+    Cancelled,
+}
+
+impl<Err: fmt::Display> fmt::Display for ExecuteError<Err> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Failed { node, error } => write!(f, "node {node} failed: {error}"),
+            Self::Cancelled => write!(f, "execution is cancelled"),
+        }
+    }
+}
+
+impl<Err: std::error::Error> std::error::Error for ExecuteError<Err> {}
+
+/// 多线程的图执行器，节点的所有前驱执行完后立即在线程池中执行。
+#[derive(Clone, Default, Debug)]
+pub struct Executor {
+    /// 线程数，为 0 时使用系统可用的并行度。
+    pub threads: usize,
+    /// 取消令牌。
+    pub cancel: CancelToken,
+}
+
+/// 边的存储位置。
+enum Slot<'a, E> {
+    /// 全图输入和局部边，执行期间只读。
+    Fixed(&'a E),
+    /// 节点的第几个出边。
+    Output(usize, usize),
+}
+
+/// 就绪队列。
+struct Queue {
+    /// 可以执行的节点。
+    ready: Vec<usize>,
+    /// 所有节点执行完，或执行出错、被取消。
+    stop: bool,
+}
+
+/// 线程因 panic 退出时通知其他线程停止，避免它们永远等待。
+struct StopOnPanic<'a>(&'a Mutex<Queue>, &'a Condvar);
+
+impl Drop for StopOnPanic<'_> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.lock().unwrap_or_else(PoisonError::into_inner).stop = true;
+            self.1.notify_all();
+        }
+    }
+}
+
+impl Executor {
+    /// 执行图中的所有节点，`f` 读取节点的入边，写入节点的出边。
+    ///
+    /// 节点之间的依赖由拓扑结构确定，没有依赖关系的节点可能并行执行。
+    /// 第一个返回错误的节点使执行停止，已开始的节点会执行完。
+    pub fn run<N, E, Err>(
+        &self,
+        graph: &mut Graph<N, E>,
+        f: impl Fn(&N, &[&E], &mut [E]) -> Result<(), Err> + Sync,
+    ) -> Result<(), ExecuteError<Err>>
+    where
+        N: Sync,
+        E: Send + Sync,
+        Err: Send,
+    {
+        if self.cancel.is_cancelled() {
+            return Err(ExecuteError::Cancelled);
+        }
+        let Graph {
+            topology,
+            nodes,
+            edges,
+            ..
+        } = graph;
+        let nodes_len = topology.nodes_len();
+        if nodes_len == 0 {
+            return Ok(());
+        }
+
+        // 划分边的存储，每个节点的出边单独加锁
+        let mut slots = Vec::with_capacity(edges.len());
+        let mut outputs = Vec::with_capacity(nodes_len);
+        let (global_inputs, mut rest) = edges.split_at_mut(topology.global_inputs_len());
+        slots.extend(global_inputs.iter().map(Slot::Fixed));
+        for (i, _, range) in &*topology {
+            let locals_len = topology.nodes[i.index()].local_edges_len();
+            let (locals, tail) = std::mem::take(&mut rest).split_at_mut(locals_len);
+            let (node_outputs, tail) = tail.split_at_mut(range.len());
+            rest = tail;
+            slots.extend(locals.iter().map(Slot::Fixed));
+            slots.extend((0..node_outputs.len()).map(|k| Slot::Output(i.index(), k)));
+            outputs.push(RwLock::new(node_outputs));
+        }
+
+        // 统计依赖
+        let mut pending = vec![0; nodes_len];
+        let mut successors = vec![Vec::new(); nodes_len];
+        let mut inputs = Vec::with_capacity(nodes_len);
+        for (i, node_inputs, _) in &*topology {
+            let node_inputs = node_inputs.iter().map(|e| e.0.index()).collect::<Vec<_>>();
+            for &e in &node_inputs {
+                if let Slot::Output(p, _) = slots[e] {
+                    pending[i.index()] += 1;
+                    successors[p].push(i.index());
+                }
+            }
+            inputs.push(node_inputs);
+        }
+        let ready = (0..nodes_len).rev().filter(|&i| pending[i] == 0).collect();
+        let pending = pending
+            .into_iter()
+            .map(AtomicUsize::new)
+            .collect::<Vec<_>>();
+        let remaining = AtomicUsize::new(nodes_len);
+        let queue = Mutex::new(Queue { ready, stop: false });
+        let wake = Condvar::new();
+        let error = Mutex::new(None);
+
+        let execute = |i: usize| {
+            let mut guards = Vec::new();
+            for &e in &inputs[i] {
+                if let Slot::Output(p, _) = slots[e] {
+                    if guards.iter().all(|(q, _)| *q != p) {
+                        guards.push((p, outputs[p].read().unwrap()));
+                    }
+                }
+            }
+            let args = inputs[i]
+                .iter()
+                .map(|&e| match slots[e] {
+                    Slot::Fixed(edge) => edge,
+                    Slot::Output(p, k) => &guards.iter().find(|(q, _)| *q == p).unwrap().1[k],
+                })
+                .collect::<Vec<_>>();
+            f(&nodes[i], &args, &mut outputs[i].write().unwrap())
+        };
+        let worker = || {
+            let _guard = StopOnPanic(&queue, &wake);
+            loop {
+                let i = {
+                    let mut queue = queue.lock().unwrap();
+                    loop {
+                        if self.cancel.is_cancelled() {
+                            queue.stop = true;
+                            wake.notify_all();
+                        }
+                        if queue.stop {
+                            return;
+                        }
+                        if let Some(i) = queue.ready.pop() {
+                            break i;
+                        }
+                        queue = wake.wait(queue).unwrap();
+                    }
+                };
+                match execute(i) {
+                    Ok(()) => {
+                        let mut ready = successors[i]
+                            .iter()
+                            .copied()
+                            .filter(|&j| pending[j].fetch_sub(1, Ordering::AcqRel) == 1)
+                            .collect::<Vec<_>>();
+                        let done = remaining.fetch_sub(1, Ordering::AcqRel) == 1;
+                        if !ready.is_empty() || done {
+                            let mut queue = queue.lock().unwrap();
+                            queue.ready.append(&mut ready);
+                            queue.stop |= done;
+                            wake.notify_all();
+                        }
+                    }
+                    Err(e) => {
+                        error.lock().unwrap().get_or_insert((NodeIdx::new(i), e));
+                        queue.lock().unwrap().stop = true;
+                        wake.notify_all();
+                    }
+                }
+            }
+        };
+
+        let threads = match self.threads {
+            0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            n => n,
+        }
+        .min(nodes_len);
+        thread::scope(|s| {
+            for _ in 1..threads {
+                s.spawn(worker);
+            }
+            worker();
+        });
+
+        match error.into_inner().unwrap() {
+            Some((node, error)) => Err(ExecuteError::Failed { node, error }),
+            None if remaining.into_inner() > 0 => Err(ExecuteError::Cancelled),
+            None => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn test_execute() {
+    let text = "
+        %x = input
+        %a = node#0 \"+\"(%x, %x)
+        %b = node#1 \"*\"(%x, %x)
+        %w = local
+        %c = node#2 \"+\"(%a, %w)
+        %d = node#3 \"*\"(%b, %c)
+        return %d
+    ";
+    let topo = text.parse::<Graph<String, ()>>().unwrap().topology;
+    let mut graph = Graph {
+        topology: topo,
+        nodes: vec![true, false, true, false],
+        edges: vec![0i64; 6],
+        names: None,
+    };
+    graph.edges[0] = 3;
+    graph.edges[3] = 4;
+    // 节点信息表示做加法还是乘法
+    let add_or_mul = |add: &bool, inputs: &[&i64], outputs: &mut [i64]| {
+        outputs[0] = if *add {
+            inputs[0] + inputs[1]
+        } else {
+            inputs[0] * inputs[1]
+        };
+        if outputs[0] > 100 {
+            Err(outputs[0])
+        } else {
+            Ok(())
+        }
+    };
+
+    let executor = Executor {
+        threads: 4,
+        ..Default::default()
+    };
+    assert_eq!(executor.run(&mut graph, add_or_mul), Ok(()));
+    assert_eq!(graph.edges, [3, 6, 9, 4, 10, 90]);
+
+    graph.edges[0] = 4;
+    assert_eq!(
+        executor.run(&mut graph, add_or_mul),
+        Err(ExecuteError::Failed {
+            node: NodeIdx::new(3),
+            error: 192,
+        })
+    );
+
+    executor.cancel.cancel();
+    assert_eq!(
+        executor.run(&mut graph, add_or_mul),
+        Err(ExecuteError::Cancelled)
+    );
+}
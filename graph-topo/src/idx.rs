@@ -0,0 +1,184 @@
+use std::{fmt, ops::Range};
+
+/// 图拓扑中存储序号和数量的类型。
+///
+/// 启用 `compact-index` 特性时使用 `u32`，可以在 64 位平台上将大图拓扑的内存占用减半。
+#[cfg(not(feature = "compact-index"))]
+pub(crate) type Repr = usize;
+#[cfg(feature = "compact-index")]
+pub(crate) type Repr = u32;
+
+/// 将序号或数量转换为存储类型。
+#[cfg(not(feature = "compact-index"))]
+#[inline]
+pub(crate) const fn repr(n: usize) -> Repr {
+    n
+}
+#[cfg(feature = "compact-index")]
+#[inline]
+pub(crate) const fn repr(n: usize) -> Repr {
+    assert!(
+        n <= Repr::MAX as usize,
+        "index exceeds the compact index range"
+    );
+    n as _
+}
+
+/// 将序号或数量转换为存储类型，超出范围时返回 `None`。
+#[inline]
+pub(crate) fn try_repr(n: usize) -> Option<Repr> {
+    Repr::try_from(n).ok()
+}
+
+macro_rules! define_idx {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde::Serialize, serde::Deserialize),
+            serde(transparent)
+        )]
+        pub struct $name(Repr);
+
+        impl $name {
+            /// 从序号构造。
+            ///
+            /// 启用 `compact-index` 特性时，序号超出 `u32` 范围将导致 panic。
+            #[inline]
+            pub const fn new(idx: usize) -> Self {
+                Self(repr(idx))
+            }
+
+            /// 获取序号。
+            #[inline]
+            pub const fn index(self) -> usize {
+                self.0 as _
+            }
+        }
+
+        impl From<usize> for $name {
+            #[inline]
+            fn from(idx: usize) -> Self {
+                Self::new(idx)
+            }
+        }
+
+        impl From<$name> for usize {
+            #[inline]
+            fn from(idx: $name) -> Self {
+                idx.index()
+            }
+        }
+
+        impl fmt::Display for $name {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.index().fmt(f)
+            }
+        }
+    };
+}
+
+define_idx! {
+    /// 节点序号。
+    NodeIdx
+}
+
+define_idx! {
+    /// 边序号。
+    EdgeIdx
+}
+
+impl EdgeIdx {
+    /// 从序号构造，序号超出存储类型的范围时返回 `None`。
+    #[inline]
+    pub(crate) fn try_new(idx: usize) -> Option<Self> {
+        try_repr(idx).map(Self)
+    }
+}
+
+/// 连续的边序号区间。
+#[derive(Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub struct EdgeRange {
+    start: usize,
+    end: usize,
+}
+
+impl EdgeRange {
+    #[inline]
+    pub(crate) const fn new(range: Range<usize>) -> Self {
+        Self {
+            start: range.start,
+            end: range.end,
+        }
+    }
+
+    /// 区间中第一条边。
+    #[inline]
+    pub const fn start(&self) -> EdgeIdx {
+        EdgeIdx::new(self.start)
+    }
+
+    /// 区间中最后一条边之后的边。
+    #[inline]
+    pub const fn end(&self) -> EdgeIdx {
+        EdgeIdx::new(self.end)
+    }
+
+    /// 区间是否为空。
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+
+    /// 判断区间是否包含边。
+    #[inline]
+    pub const fn contains(&self, edge: EdgeIdx) -> bool {
+        self.start <= edge.index() && edge.index() < self.end
+    }
+}
+
+impl Iterator for EdgeRange {
+    type Item = EdgeIdx;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start < self.end {
+            self.start += 1;
+            Some(EdgeIdx::new(self.start - 1))
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end.saturating_sub(self.start);
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for EdgeRange {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.start < self.end {
+            self.end -= 1;
+            Some(EdgeIdx::new(self.end))
+        } else {
+            None
+        }
+    }
+}
+
+impl ExactSizeIterator for EdgeRange {}
+
+#[test]
+fn test_repr() {
+    use std::mem::size_of;
+
+    assert_eq!(size_of::<NodeIdx>(), size_of::<Repr>());
+    assert_eq!(size_of::<crate::OutputEdge>(), size_of::<Repr>());
+    #[cfg(feature = "compact-index")]
+    assert_eq!(size_of::<Repr>(), 4);
+}
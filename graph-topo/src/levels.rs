@@ -0,0 +1,145 @@
+use crate::{GraphTopo, NodeIdx};
+
+/// 节点的层次划分结果。
+///
+/// 节点的最早层次（ASAP）是它到图入口的最长依赖链长度，最晚层次（ALAP）是在不增加总层数的前提下它最晚可以所在的层次。
+/// 按最早层次分组，同一层的节点互不依赖，每一层只依赖之前的层。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Levels {
+    /// 每个节点的最早层次。
+    asap: Vec<usize>,
+    /// 每个节点的最晚层次。
+    alap: Vec<usize>,
+    /// 每一层的节点在 `nodes` 中的起始位置。
+    offsets: Vec<usize>,
+    /// 按最早层次排列的节点。
+    nodes: Vec<NodeIdx>,
+}
+
+impl GraphTopo {
+    /// 将节点按依赖关系划分为层次。
+    pub fn levels(&self) -> Levels {
+        let mut producer = vec![None; self.global_inputs_len];
+        let mut asap = Vec::<usize>::with_capacity(self.nodes.len());
+        for (i, inputs, outputs) in self {
+            let level = inputs
+                .iter()
+                .filter_map(|e| producer.get(e.0.index()).copied().flatten())
+                .map(|p: NodeIdx| asap[p.index()] + 1)
+                .max()
+                .unwrap_or(0);
+            asap.push(level);
+            producer.resize(outputs.end().index(), None);
+            for e in outputs {
+                producer[e.index()] = Some(i);
+            }
+        }
+        let depth = asap.iter().max().map_or(0, |&l| l + 1);
+
+        let mut alap = vec![depth.saturating_sub(1); self.nodes.len()];
+        for (i, inputs, _) in self.traverse().rev() {
+            let level = alap[i.index()];
+            for p in inputs.iter().filter_map(|e| producer[e.0.index()]) {
+                alap[p.index()] = alap[p.index()].min(level - 1);
+            }
+        }
+
+        let mut offsets = vec![0; depth + 1];
+        for &level in &asap {
+            offsets[level + 1] += 1;
+        }
+        for k in 1..offsets.len() {
+            offsets[k] += offsets[k - 1];
+        }
+        let mut nodes = vec![NodeIdx::new(0); self.nodes.len()];
+        let mut cursor = offsets.clone();
+        for (i, &level) in asap.iter().enumerate() {
+            nodes[cursor[level]] = NodeIdx::new(i);
+            cursor[level] += 1;
+        }
+
+        Levels {
+            asap,
+            alap,
+            offsets,
+            nodes,
+        }
+    }
+}
+
+impl Levels {
+    /// 层数，即图中最长依赖链的节点数。
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// 最宽一层的节点数，可以作为图的并行度的估计。
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.offsets
+            .windows(2)
+            .map(|w| w[1] - w[0])
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// 第 `k` 层的节点，按节点序号排列。
+    #[inline]
+    pub fn level(&self, k: usize) -> &[NodeIdx] {
+        &self.nodes[self.offsets[k]..self.offsets[k + 1]]
+    }
+
+    /// 按顺序迭代每一层的节点。
+    #[inline]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &[NodeIdx]> + ExactSizeIterator + '_ {
+        (0..self.depth()).map(|k| self.level(k))
+    }
+
+    /// 节点的最早层次。
+    #[inline]
+    pub fn asap(&self, i: NodeIdx) -> usize {
+        self.asap[i.index()]
+    }
+
+    /// 节点的最晚层次。
+    #[inline]
+    pub fn alap(&self, i: NodeIdx) -> usize {
+        self.alap[i.index()]
+    }
+
+    /// 节点的松弛度，即可以推迟的层数，为 0 的节点在关键路径上。
+    #[inline]
+    pub fn slack(&self, i: NodeIdx) -> usize {
+        self.alap[i.index()] - self.asap[i.index()]
+    }
+}
+
+#[test]
+fn test_levels() {
+    let text = "
+        %x = input
+        %a = node#0 \"A\"(%x)
+        %b = node#1 \"B\"(%a)
+        %c = node#2 \"C\"(%b)
+        %d = node#3 \"D\"(%x)
+        %w = local
+        %e = node#4 \"E\"(%c, %d, %w)
+        return %e
+    ";
+    let topo = text.parse::<crate::Graph<String, ()>>().unwrap().topology;
+    let levels = topo.levels();
+    let [a, b, c, d, e] = [0, 1, 2, 3, 4].map(NodeIdx::new);
+    assert_eq!(levels.depth(), 4);
+    assert_eq!(levels.width(), 2);
+    assert_eq!(
+        levels.iter().collect::<Vec<_>>(),
+        [&[a, d][..], &[b], &[c], &[e]]
+    );
+    assert_eq!(levels.iter().flatten().count(), topo.nodes_len());
+    assert_eq!((levels.asap(d), levels.alap(d), levels.slack(d)), (0, 2, 2));
+    for i in [a, b, c, e] {
+        assert_eq!(levels.slack(i), 0);
+    }
+    assert_eq!(GraphTopo::default().levels().depth(), 0);
+}
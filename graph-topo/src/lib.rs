@@ -14,132 +14,185 @@
 
 #![deny(warnings, missing_docs)]
 
+mod binary;
+mod builder;
 mod container;
+mod dot;
+mod executor;
+mod idx;
+mod levels;
+mod liveness;
+mod planner;
+mod rewrite;
 mod searcher;
+#[cfg(feature = "serde")]
+mod serialize;
+mod text;
+mod validate;
 
-pub use container::{Graph, GraphTopo};
+pub use binary::{Codec, DecodeError};
+pub use builder::{
+    Bfs, BuildError, Builder, Dfs, MinLiveBytes, Ordered, Pruned, Schedule, ScheduleContext,
+};
+pub use container::{EdgeKind, Graph, GraphTopo, Iter, Names, OutputEdge};
+pub use executor::{CancelToken, ExecuteError, Executor};
+pub use idx::{EdgeIdx, EdgeRange, NodeIdx};
+pub use levels::Levels;
+pub use liveness::{Liveness, Point};
+pub use planner::{MemoryPlan, MemoryPlanner, PlanStrategy};
+pub use rewrite::{ComposeError, EditError, Origins, Remap};
 pub use searcher::{Edge, Node, Searcher};
+pub use text::{ParseError, TextPayload};
+pub use validate::TopoError;
 
-// #[test]
-// fn test() {
-//     use std::collections::{HashMap, HashSet};
+#[test]
+fn test() {
+    use std::collections::HashSet;
 
-//     let graph = Builder {
-//         topology: HashMap::from([
-//             ("A", (vec!["a", "b"], vec!["c", "d"])),
-//             ("B", (vec!["d", "e"], vec!["f"])),
-//             ("C", (vec!["f", "c"], vec!["z"])),
-//         ]),
-//         global_inputs: vec!["a"],
-//         global_outputs: vec!["z"],
-//         nodes: HashMap::from([("A", "*0"), ("B", "*1"), ("C", "*2")]),
-//         edges: HashMap::from([("a", "|0"), ("b", "|1"), ("e", "|4"), ("z", "!")]),
-//     }
-//     .build();
+    let mut builder = Builder::default();
+    builder.add_global_input("a").unwrap();
+    builder
+        .add_node("A", vec!["a", "b"], vec!["c", "d"], "*0")
+        .unwrap();
+    builder
+        .add_node("B", vec!["d", "e"], vec!["f"], "*1")
+        .unwrap();
+    builder
+        .add_node("C", vec!["f", "c"], vec!["z"], "*2")
+        .unwrap();
+    builder.mark_global_output("z");
+    for (edge, info) in [("a", "|0"), ("b", "|1"), ("e", "|4"), ("z", "!")] {
+        builder.set_edge_info(edge, info);
+    }
+    let graph = builder.build().unwrap();
 
-//     let Graph {
-//         topology,
-//         nodes: _,
-//         edges,
-//     } = graph;
+    let searcher = Searcher::from(&graph.topology);
+    {
+        let inputs = searcher.global_inputs();
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs[0].index(), EdgeIdx::new(0));
+        assert_eq!(graph[inputs[0].index()], "|0");
 
-//     let searcher = Searcher::from(&topology);
-//     {
-//         let inputs = searcher.global_inputs();
-//         assert_eq!(inputs.len(), 1);
-//         assert_eq!(inputs[0].index(), 0);
-//         assert_eq!(edges[inputs[0].index()], "|0");
+        let outputs = searcher.global_outputs();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].index(), EdgeIdx::new(6));
+        assert_eq!(graph[outputs[0].index()], "!");
 
-//         let outputs = searcher.global_outputs();
-//         assert_eq!(outputs.len(), 1);
-//         assert_eq!(outputs[0].index(), 6);
-//         assert_eq!(edges[outputs[0].index()], "!");
+        let local_edges = searcher
+            .local_edges()
+            .iter()
+            .map(|x| x.index())
+            .collect::<HashSet<_>>();
+        assert_eq!(local_edges.len(), 2);
+        assert_eq!(
+            local_edges,
+            HashSet::from([EdgeIdx::new(1), EdgeIdx::new(4)])
+        );
+        assert_eq!(
+            local_edges
+                .iter()
+                .map(|i| graph[*i])
+                .collect::<HashSet<_>>(),
+            HashSet::from(["|1", "|4"])
+        );
+    }
+    {
+        let nodes = searcher.nodes();
+        assert_eq!(nodes.len(), 3);
 
-//         let local_edges = searcher
-//             .local_edges()
-//             .iter()
-//             .map(|x| x.index())
-//             .collect::<HashSet<_>>();
-//         assert_eq!(local_edges.len(), 2);
-//         assert_eq!(local_edges, HashSet::from([1, 4]));
-//         assert_eq!(
-//             local_edges
-//                 .iter()
-//                 .map(|i| edges[*i])
-//                 .collect::<HashSet<_>>(),
-//             HashSet::from(["|1", "|4"])
-//         );
-//     }
-//     {
-//         let nodes = searcher.nodes();
-//         assert_eq!(nodes.len(), 3);
+        let a = nodes.get(NodeIdx::new(0));
+        assert_eq!(a.inputs().len(), 2);
+        assert_eq!(a.inputs()[0].index(), EdgeIdx::new(0));
+        assert_eq!(a.inputs()[1].index(), EdgeIdx::new(1));
+        assert_eq!(a.outputs().len(), 2);
+        assert_eq!(a.outputs()[0].index(), EdgeIdx::new(2));
+        assert_eq!(a.outputs()[1].index(), EdgeIdx::new(3));
+        assert_eq!(
+            a.predecessors()
+                .iter()
+                .map(Node::index)
+                .collect::<HashSet<_>>(),
+            HashSet::from([])
+        );
+        assert_eq!(
+            a.successors()
+                .iter()
+                .map(Node::index)
+                .collect::<HashSet<_>>(),
+            HashSet::from([NodeIdx::new(1), NodeIdx::new(2)])
+        );
 
-//         let a = nodes.get(0);
-//         assert_eq!(a.inputs().len(), 2);
-//         assert_eq!(a.inputs()[0].index(), 0);
-//         assert_eq!(a.inputs()[1].index(), 1);
-//         assert_eq!(a.outputs().len(), 2);
-//         assert_eq!(a.outputs()[0].index(), 2);
-//         assert_eq!(a.outputs()[1].index(), 3);
-//         assert_eq!(
-//             a.predecessors()
-//                 .iter()
-//                 .map(Node::index)
-//                 .collect::<HashSet<_>>(),
-//             HashSet::from([])
-//         );
-//         assert_eq!(
-//             a.successors()
-//                 .iter()
-//                 .map(Node::index)
-//                 .collect::<HashSet<_>>(),
-//             HashSet::from([1, 2])
-//         );
+        let b = nodes.get(NodeIdx::new(1));
+        assert_eq!(b.inputs().len(), 2);
+        assert_eq!(b.inputs()[0].index(), EdgeIdx::new(3));
+        assert_eq!(b.inputs()[1].index(), EdgeIdx::new(4));
+        assert_eq!(b.outputs().len(), 1);
+        assert_eq!(b.outputs()[0].index(), EdgeIdx::new(5));
+        assert_eq!(
+            b.predecessors()
+                .iter()
+                .map(Node::index)
+                .collect::<HashSet<_>>(),
+            HashSet::from([NodeIdx::new(0)])
+        );
+        assert_eq!(
+            b.successors()
+                .iter()
+                .map(Node::index)
+                .collect::<HashSet<_>>(),
+            HashSet::from([NodeIdx::new(2)])
+        );
 
-//         let b = nodes.get(1);
-//         assert_eq!(b.inputs().len(), 2);
-//         assert_eq!(b.inputs()[0].index(), 3);
-//         assert_eq!(b.inputs()[1].index(), 4);
-//         assert_eq!(b.outputs().len(), 1);
-//         assert_eq!(b.outputs()[0].index(), 5);
-//         assert_eq!(
-//             b.predecessors()
-//                 .iter()
-//                 .map(Node::index)
-//                 .collect::<HashSet<_>>(),
-//             HashSet::from([0])
-//         );
-//         assert_eq!(
-//             b.successors()
-//                 .iter()
-//                 .map(Node::index)
-//                 .collect::<HashSet<_>>(),
-//             HashSet::from([2])
-//         );
+        let c = nodes.get(NodeIdx::new(2));
+        assert_eq!(c.inputs().len(), 2);
+        assert_eq!(c.inputs()[0].index(), EdgeIdx::new(5));
+        assert_eq!(c.inputs()[1].index(), EdgeIdx::new(2));
+        assert_eq!(c.outputs().len(), 1);
+        assert_eq!(c.outputs()[0].index(), EdgeIdx::new(6));
+        assert_eq!(
+            c.predecessors()
+                .iter()
+                .map(Node::index)
+                .collect::<HashSet<_>>(),
+            HashSet::from([NodeIdx::new(0), NodeIdx::new(1)])
+        );
+        assert_eq!(
+            c.successors()
+                .iter()
+                .map(Node::index)
+                .collect::<HashSet<_>>(),
+            HashSet::from([])
+        );
+        assert!(c.to_global_outputs());
+        assert!(c.outputs()[0].is_global_output());
+    }
+    {
+        let edges = searcher.edges();
+        assert_eq!(edges.len(), 7);
+    }
+}
 
-//         let c = nodes.get(2);
-//         assert_eq!(c.inputs().len(), 2);
-//         assert_eq!(c.inputs()[0].index(), 5);
-//         assert_eq!(c.inputs()[1].index(), 2);
-//         assert_eq!(c.outputs().len(), 1);
-//         assert_eq!(c.outputs()[0].index(), 6);
-//         assert_eq!(
-//             c.predecessors()
-//                 .iter()
-//                 .map(Node::index)
-//                 .collect::<HashSet<_>>(),
-//             HashSet::from([0, 1])
-//         );
-//         assert_eq!(
-//             c.successors()
-//                 .iter()
-//                 .map(Node::index)
-//                 .collect::<HashSet<_>>(),
-//             HashSet::from([usize::MAX])
-//         );
-//     }
-//     {
-//         let edges = searcher.edges();
-//         assert_eq!(edges.len(), 7);
-//     }
-// }
+#[test]
+fn test_into_builder() {
+    let mut builder = Builder::default();
+    builder.add_global_input("a").unwrap();
+    builder
+        .add_node("A", vec!["a", "b"], vec!["c", "d"], "*0")
+        .unwrap();
+    builder
+        .add_node("B", vec!["e", "d", "e"], vec!["f"], "*1")
+        .unwrap();
+    builder
+        .add_node("C", vec!["f", "c"], vec!["z"], "*2")
+        .unwrap();
+    builder.mark_global_output("z");
+    builder.mark_global_output("c");
+    for (edge, info) in [("a", "|0"), ("b", "|1"), ("e", "|4"), ("z", "!")] {
+        builder.set_edge_info(edge, info);
+    }
+    let graph = builder.build().unwrap();
+    let rebuilt = graph.clone().into_builder().build().unwrap();
+    assert_eq!(rebuilt.topology, graph.topology);
+    assert_eq!(rebuilt.nodes, graph.nodes);
+    assert_eq!(rebuilt.edges, graph.edges);
+}
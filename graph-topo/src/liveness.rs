@@ -0,0 +1,146 @@
+use crate::{EdgeIdx, GraphTopo, NodeIdx};
+
+/// 拓扑序中的位置。
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Point {
+    /// 所有节点之前。
+    Start,
+    /// 节点执行时。
+    Node(NodeIdx),
+    /// 所有节点之后。
+    End,
+}
+
+/// 边的活跃区间分析结果。
+///
+/// 边在定义点和最后使用点之间（包含两端）是活跃的：
+/// 全图输入定义于 [`Point::Start`]，局部边和节点出边定义于所属的节点；
+/// 全图输出活跃到 [`Point::End`]，没有被使用的边只在定义点活跃。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Liveness {
+    /// 每条边的定义点和最后使用点。
+    lifetimes: Vec<(Point, Point)>,
+    /// 每个节点之后可以释放的边在 `released` 中的起始位置。
+    released_offsets: Vec<usize>,
+    /// 按最后使用的节点排列的边。
+    released: Vec<EdgeIdx>,
+}
+
+impl GraphTopo {
+    /// 在拓扑序上分析所有边的活跃区间。
+    pub fn liveness(&self) -> Liveness {
+        let mut lifetimes = vec![(Point::Start, Point::Start); self.global_inputs_len];
+        for (i, inputs, outputs) in self {
+            let def = Point::Node(i);
+            lifetimes.resize(outputs.end().index(), (def, def));
+            for input in inputs {
+                lifetimes[input.0.index()].1 = def;
+            }
+        }
+        for output in self.global_outputs() {
+            lifetimes[output.0.index()].1 = Point::End;
+        }
+
+        let mut released_offsets = vec![0; self.nodes.len() + 1];
+        for (_, last) in &lifetimes {
+            if let Point::Node(i) = last {
+                released_offsets[i.index() + 1] += 1;
+            }
+        }
+        for i in 1..released_offsets.len() {
+            released_offsets[i] += released_offsets[i - 1];
+        }
+        let mut released = vec![EdgeIdx::new(0); released_offsets[self.nodes.len()]];
+        let mut cursor = released_offsets.clone();
+        for (e, (_, last)) in lifetimes.iter().enumerate() {
+            if let Point::Node(i) = last {
+                released[cursor[i.index()]] = EdgeIdx::new(e);
+                cursor[i.index()] += 1;
+            }
+        }
+
+        Liveness {
+            lifetimes,
+            released_offsets,
+            released,
+        }
+    }
+}
+
+impl Liveness {
+    /// 边的数量。
+    #[inline]
+    pub fn edges_len(&self) -> usize {
+        self.lifetimes.len()
+    }
+
+    /// 边的定义点。
+    #[inline]
+    pub fn def(&self, e: EdgeIdx) -> Point {
+        self.lifetimes[e.index()].0
+    }
+
+    /// 边的最后使用点。
+    #[inline]
+    pub fn last_use(&self, e: EdgeIdx) -> Point {
+        self.lifetimes[e.index()].1
+    }
+
+    /// 判断边在某个位置是否活跃。
+    #[inline]
+    pub fn is_live(&self, e: EdgeIdx, at: Point) -> bool {
+        let (def, last) = self.lifetimes[e.index()];
+        def <= at && at <= last
+    }
+
+    /// 判断两条边的活跃区间是否重叠。
+    #[inline]
+    pub fn overlaps(&self, a: EdgeIdx, b: EdgeIdx) -> bool {
+        let (a_def, a_last) = self.lifetimes[a.index()];
+        let (b_def, b_last) = self.lifetimes[b.index()];
+        a_def <= b_last && b_def <= a_last
+    }
+
+    /// 最后被节点使用的边，节点执行完后就可以释放。
+    #[inline]
+    pub fn released_after(&self, i: NodeIdx) -> &[EdgeIdx] {
+        &self.released[self.released_offsets[i.index()]..self.released_offsets[i.index() + 1]]
+    }
+}
+
+#[test]
+fn test_liveness() {
+    use crate::Builder;
+
+    let mut builder = Builder::<_, _, _, ()>::default();
+    builder.add_global_input("a").unwrap();
+    builder
+        .add_node("A", vec!["a", "w"], vec!["b", "c"], ())
+        .unwrap();
+    builder.add_node("B", vec!["b"], vec!["d"], ()).unwrap();
+    builder
+        .add_node("C", vec!["d", "w"], vec!["e"], ())
+        .unwrap();
+    builder.mark_global_output("e");
+    builder.set_edge_info("w", ());
+    let topo = builder.build().unwrap().topology;
+    let liveness = topo.liveness();
+
+    let [a, w, b, c, d, e] = [0, 1, 2, 3, 4, 5].map(EdgeIdx::new);
+    let [n0, n1, n2] = [0, 1, 2].map(|i| Point::Node(NodeIdx::new(i)));
+    assert_eq!(liveness.edges_len(), 6);
+    assert_eq!((liveness.def(a), liveness.last_use(a)), (Point::Start, n0));
+    assert_eq!((liveness.def(w), liveness.last_use(w)), (n0, n2));
+    assert_eq!((liveness.def(b), liveness.last_use(b)), (n0, n1));
+    assert_eq!((liveness.def(c), liveness.last_use(c)), (n0, n0));
+    assert_eq!((liveness.def(d), liveness.last_use(d)), (n1, n2));
+    assert_eq!((liveness.def(e), liveness.last_use(e)), (n2, Point::End));
+
+    assert!(liveness.overlaps(w, d));
+    assert!(!liveness.overlaps(a, d));
+    assert!(liveness.is_live(w, n1));
+    assert!(!liveness.is_live(b, n2));
+    assert_eq!(liveness.released_after(NodeIdx::new(0)), [a, c]);
+    assert_eq!(liveness.released_after(NodeIdx::new(1)), [b]);
+    assert_eq!(liveness.released_after(NodeIdx::new(2)), [w, d]);
+}
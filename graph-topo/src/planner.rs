@@ -0,0 +1,381 @@
+use crate::{EdgeIdx, GraphTopo, Point};
+use std::alloc::Layout;
+
+/// 内存规划的策略。
+///
+/// 边只需要避开与之活跃区间重叠的已分配边，两种策略的分配顺序和选择空隙的方式不同。
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub enum PlanStrategy {
+    /// 按尺寸从大到小分配，每条边放在能容纳它的最低偏移。
+    #[default]
+    GreedyBySize,
+    /// 按定义的拓扑序分配，每条边放进能容纳它的最小空隙。
+    BestFit,
+}
+
+/// 静态内存规划器，为边分配同一块内存池中的偏移。
+///
+/// 活跃区间重叠的两条边不会共享内存。
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct MemoryPlanner {
+    /// 分配策略。
+    pub strategy: PlanStrategy,
+    /// 全图输入是否放在内存池之外。
+    pub pin_global_inputs: bool,
+    /// 全图输出是否放在内存池之外。
+    pub pin_global_outputs: bool,
+}
+
+/// 内存规划的结果。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MemoryPlan {
+    /// 每条边在内存池中的偏移，固定在内存池之外的边为 `None`。
+    offsets: Vec<Option<usize>>,
+    /// 内存池的大小。
+    peak: usize,
+    /// 内存池需要的对齐。
+    align: usize,
+}
+
+impl MemoryPlanner {
+    /// 为拓扑中的所有边规划内存，`layout` 给出每条边的尺寸和对齐。
+    ///
+    /// 与边活跃区间重叠的边通过区间树查询，复杂度为 O(E log E + Σ k log k)，
+    /// 其中 E 是边数，k 是与每条边活跃区间重叠的边数。
+    pub fn plan(&self, topo: &GraphTopo, mut layout: impl FnMut(EdgeIdx) -> Layout) -> MemoryPlan {
+        let liveness = topo.liveness();
+        let edges_len = liveness.edges_len();
+        let mut pinned = vec![false; edges_len];
+        if self.pin_global_inputs {
+            for e in topo.global_inputs() {
+                pinned[e.index()] = true;
+            }
+        }
+        if self.pin_global_outputs {
+            for e in topo.global_outputs() {
+                pinned[e.0.index()] = true;
+            }
+        }
+
+        let layouts = (0..edges_len)
+            .map(|e| layout(EdgeIdx::new(e)))
+            .collect::<Vec<_>>();
+        let mut order = (0..edges_len)
+            .filter(|&e| !pinned[e] && layouts[e].size() > 0)
+            .map(EdgeIdx::new)
+            .collect::<Vec<_>>();
+        match self.strategy {
+            PlanStrategy::GreedyBySize => {
+                order.sort_by_key(|e| std::cmp::Reverse(layouts[e.index()].size()))
+            }
+            PlanStrategy::BestFit => order.sort_by_key(|&e| liveness.def(e)),
+        }
+
+        // 将拓扑序中的位置映射为整数时刻
+        let end = topo.nodes_len() + 1;
+        let time = |p: Point| match p {
+            Point::Start => 0,
+            Point::Node(i) => i.index() + 1,
+            Point::End => end,
+        };
+        let lifetime = |e: EdgeIdx| (time(liveness.def(e)), time(liveness.last_use(e)));
+        let tree = IntervalTree::new(order.iter().map(|&e| (lifetime(e), e)).collect(), end);
+
+        // 尺寸为 0 的边不占用内存，偏移总是 0
+        let mut offsets = (0..edges_len)
+            .map(|e| (!pinned[e] && layouts[e].size() == 0).then_some(0))
+            .collect::<Vec<_>>();
+        let mut neighbors = Vec::new();
+        let mut peak = 0;
+        let align = layouts
+            .iter()
+            .zip(&pinned)
+            .filter(|(_, pinned)| !**pinned)
+            .map(|(layout, _)| layout.align())
+            .max()
+            .unwrap_or(1);
+        for e in order {
+            let layout = layouts[e.index()];
+            neighbors.clear();
+            let (start, end) = lifetime(e);
+            tree.query(start, end, &mut |other| {
+                if let Some(offset) = offsets[other.index()] {
+                    neighbors.push((offset, offset + layouts[other.index()].size()));
+                }
+            });
+            neighbors.sort_unstable();
+            let offset = match self.strategy {
+                PlanStrategy::GreedyBySize => first_gap(&neighbors, layout),
+                PlanStrategy::BestFit => best_gap(&neighbors, layout),
+            };
+            peak = peak.max(offset + layout.size());
+            offsets[e.index()] = Some(offset);
+        }
+        MemoryPlan {
+            offsets,
+            peak,
+            align,
+        }
+    }
+}
+
+/// 在按偏移排列的、与边活跃区间重叠的已分配内存之间，找到能容纳边的最低偏移。
+fn first_gap(neighbors: &[(usize, usize)], layout: Layout) -> usize {
+    let align_up = |n: usize| n.next_multiple_of(layout.align());
+    let mut prev_end = 0;
+    for &(start, end) in neighbors {
+        let offset = align_up(prev_end);
+        if offset + layout.size() <= start {
+            return offset;
+        }
+        prev_end = prev_end.max(end);
+    }
+    align_up(prev_end)
+}
+
+/// 在按偏移排列的、与边活跃区间重叠的已分配内存之间，找到能容纳边的最小空隙。
+fn best_gap(neighbors: &[(usize, usize)], layout: Layout) -> usize {
+    let align_up = |n: usize| n.next_multiple_of(layout.align());
+    let mut best = None::<(usize, usize)>;
+    let mut prev_end = 0;
+    for &(start, end) in neighbors {
+        let offset = align_up(prev_end);
+        if offset + layout.size() <= start {
+            let gap = start - prev_end;
+            if best.is_none_or(|(best_gap, _)| gap < best_gap) {
+                best = Some((gap, offset));
+            }
+        }
+        prev_end = prev_end.max(end);
+    }
+    best.map_or_else(|| align_up(prev_end), |(_, offset)| offset)
+}
+
+/// 活跃区间，包含两端的时刻。
+type Interval = ((usize, usize), EdgeIdx);
+
+/// 以时刻为键的区间树，用于查询与一个区间重叠的所有边。
+struct IntervalTree(Vec<TreeNode>);
+
+/// 区间树的节点，保存包含中心时刻的区间。
+struct TreeNode {
+    center: usize,
+    /// 按起点升序排列的区间。
+    by_start: Vec<Interval>,
+    /// 按终点降序排列的区间。
+    by_end: Vec<Interval>,
+    /// 完全在中心之前和之后的区间构成的子树。
+    children: [Option<usize>; 2],
+}
+
+impl IntervalTree {
+    /// 用 `[0, end]` 中的区间建立区间树。
+    fn new(intervals: Vec<Interval>, end: usize) -> Self {
+        let mut tree = Self(Vec::new());
+        tree.build(intervals, 0, end);
+        tree
+    }
+
+    fn build(&mut self, intervals: Vec<Interval>, lo: usize, hi: usize) -> Option<usize> {
+        if intervals.is_empty() {
+            return None;
+        }
+        let center = lo + (hi - lo) / 2;
+        let mut before = Vec::new();
+        let mut after = Vec::new();
+        let mut by_start = Vec::new();
+        for interval in intervals {
+            let ((start, end), _) = interval;
+            if end < center {
+                before.push(interval)
+            } else if start > center {
+                after.push(interval)
+            } else {
+                by_start.push(interval)
+            }
+        }
+        by_start.sort_unstable_by_key(|((start, _), _)| *start);
+        let mut by_end = by_start.clone();
+        by_end.sort_unstable_by_key(|((_, end), _)| std::cmp::Reverse(*end));
+
+        let id = self.0.len();
+        self.0.push(TreeNode {
+            center,
+            by_start,
+            by_end,
+            children: [None, None],
+        });
+        // 子树非空时，其中的区间保证了子树的时刻范围非空
+        let before = self.build(before, lo, center.saturating_sub(1));
+        let after = self.build(after, center + 1, hi);
+        self.0[id].children = [before, after];
+        Some(id)
+    }
+
+    /// 对每个与 `[start, end]` 重叠的区间调用 `f`。
+    fn query(&self, start: usize, end: usize, f: &mut impl FnMut(EdgeIdx)) {
+        if !self.0.is_empty() {
+            self.visit(0, start, end, f)
+        }
+    }
+
+    fn visit(&self, id: usize, start: usize, end: usize, f: &mut impl FnMut(EdgeIdx)) {
+        let node = &self.0[id];
+        let [before, after] = node.children;
+        if end < node.center {
+            for (_, e) in node.by_start.iter().take_while(|((s, _), _)| *s <= end) {
+                f(*e)
+            }
+            if let Some(before) = before {
+                self.visit(before, start, end, f)
+            }
+        } else if start > node.center {
+            for (_, e) in node.by_end.iter().take_while(|((_, t), _)| *t >= start) {
+                f(*e)
+            }
+            if let Some(after) = after {
+                self.visit(after, start, end, f)
+            }
+        } else {
+            for (_, e) in &node.by_start {
+                f(*e)
+            }
+            for child in [before, after].into_iter().flatten() {
+                self.visit(child, start, end, f)
+            }
+        }
+    }
+}
+
+impl MemoryPlan {
+    /// 边在内存池中的偏移，固定在内存池之外的边为 `None`。
+    #[inline]
+    pub fn offset(&self, e: EdgeIdx) -> Option<usize> {
+        self.offsets[e.index()]
+    }
+
+    /// 内存池的峰值大小。
+    #[inline]
+    pub const fn peak(&self) -> usize {
+        self.peak
+    }
+
+    /// 内存池需要的对齐。
+    #[inline]
+    pub const fn align(&self) -> usize {
+        self.align
+    }
+
+    /// 内存池的布局。
+    #[inline]
+    pub fn layout(&self) -> Layout {
+        Layout::from_size_align(self.peak, self.align).unwrap()
+    }
+}
+
+#[test]
+fn test_plan() {
+    use crate::Builder;
+
+    // a -> A -> b -> B -> c -> C -> d
+    //      +---> e ----------> +
+    let mut builder = Builder::<_, _, _, ()>::default();
+    builder.add_global_input("a").unwrap();
+    builder
+        .add_node("A", vec!["a"], vec!["b", "e"], ())
+        .unwrap();
+    builder.add_node("B", vec!["b"], vec!["c"], ()).unwrap();
+    builder
+        .add_node("C", vec!["c", "e"], vec!["d"], ())
+        .unwrap();
+    builder.mark_global_output("d");
+    let topo = builder.build().unwrap().topology;
+    let sizes = [64, 32, 16, 32, 8];
+    let layout = |e: EdgeIdx| Layout::from_size_align(sizes[e.index()], 8).unwrap();
+
+    let liveness = topo.liveness();
+    for strategy in [PlanStrategy::GreedyBySize, PlanStrategy::BestFit] {
+        for pin in [false, true] {
+            let planner = MemoryPlanner {
+                strategy,
+                pin_global_inputs: pin,
+                pin_global_outputs: pin,
+            };
+            let plan = planner.plan(&topo, layout);
+            assert_eq!(plan.align(), 8);
+            for a in (0..5).map(EdgeIdx::new) {
+                let Some(a_offset) = plan.offset(a) else {
+                    assert!(pin && [0, 4].contains(&a.index()));
+                    continue;
+                };
+                assert!(a_offset + sizes[a.index()] <= plan.peak());
+                for b in (0..a.index()).map(EdgeIdx::new) {
+                    let Some(b_offset) = plan.offset(b) else {
+                        continue;
+                    };
+                    if liveness.overlaps(a, b) {
+                        assert!(
+                            a_offset + sizes[a.index()] <= b_offset
+                                || b_offset + sizes[b.index()] <= a_offset
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // c 复用 a 的内存，峰值为 a + b + e。
+    let plan = MemoryPlanner::default().plan(&topo, layout);
+    assert_eq!(plan.peak(), 64 + 32 + 16);
+    assert_eq!(plan.offset(EdgeIdx::new(3)), Some(0));
+
+    // 空隙 [0, 32) 和 [40, 48) 都能容纳 8 字节，贪心取最低的偏移，最佳适应取最小的空隙
+    let neighbors = [(32, 40), (48, 56)];
+    let layout = Layout::from_size_align(8, 8).unwrap();
+    assert_eq!(first_gap(&neighbors, layout), 0);
+    assert_eq!(best_gap(&neighbors, layout), 40);
+}
+
+#[test]
+fn test_plan_wide() {
+    use crate::Builder;
+
+    // 每个节点使用前一个节点和序号减半的节点的输出，活跃区间长短不一
+    let mut builder = Builder::<_, _, _, ()>::default();
+    builder.add_global_input(0).unwrap();
+    for i in 0..200 {
+        builder
+            .add_node(i, vec![i, i / 2], vec![i + 1], ())
+            .unwrap();
+    }
+    builder.mark_global_output(200);
+    let topo = builder.build().unwrap().topology;
+    let size = |e: usize| (e * 37) % 100;
+    let layout = |e: EdgeIdx| Layout::from_size_align(size(e.index()), 4).unwrap();
+
+    let liveness = topo.liveness();
+    for strategy in [PlanStrategy::GreedyBySize, PlanStrategy::BestFit] {
+        let planner = MemoryPlanner {
+            strategy,
+            ..Default::default()
+        };
+        let plan = planner.plan(&topo, layout);
+        let range = |e: usize| {
+            let offset = plan.offset(EdgeIdx::new(e)).unwrap();
+            assert_eq!(offset % 4, 0);
+            offset..offset + size(e)
+        };
+        for a in 0..=200 {
+            assert!(range(a).end <= plan.peak());
+            for b in 0..a {
+                let (ra, rb) = (range(a), range(b));
+                if liveness.overlaps(EdgeIdx::new(a), EdgeIdx::new(b))
+                    && !ra.is_empty()
+                    && !rb.is_empty()
+                {
+                    assert!(ra.end <= rb.start || rb.end <= ra.start);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use super::{Loose, Remap};
+use crate::{Graph, NodeIdx};
+use std::{collections::HashMap, fmt};
+
+/// 组合图时发现的错误，出错时图不会被修改。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ComposeError {
+    /// 节点不存在。
+    NoSuchNode(NodeIdx),
+    /// 第一个图没有这个位置的全图输出。
+    NoSuchGlobalOutput(usize),
+    /// 第二个图没有这个位置的全图输入。
+    NoSuchGlobalInput(usize),
+    /// 第二个图的这个全图输入被连接了多次。
+    DuplicateLink(usize),
+    /// 被内联的图的全图输入数量与节点的入边数量不同。
+    InputsMismatch {
+        /// 节点的入边数量。
+        expected: usize,
+        /// 被内联的图的全图输入数量。
+        actual: usize,
+    },
+    /// 被内联的图的全图输出数量与节点的出边数量不同。
+    OutputsMismatch {
+        /// 节点的出边数量。
+        expected: usize,
+        /// 被内联的图的全图输出数量。
+        actual: usize,
+    },
+    /// 内联后没有节点，全图输出引用的局部边没有节点可以归属。
+    NoOwner,
+}
+
+impl fmt::Display for ComposeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSuchNode(node) => write!(f, "node {node} does not exist"),
+            Self::NoSuchGlobalOutput(slot) => write!(f, "global output {slot} does not exist"),
+            Self::NoSuchGlobalInput(slot) => write!(f, "global input {slot} does not exist"),
+            Self::DuplicateLink(slot) => write!(f, "global input {slot} is linked more than once"),
+            Self::InputsMismatch { expected, actual } => {
+                write!(f, "expected {expected} global inputs, found {actual}")
+            }
+            Self::OutputsMismatch { expected, actual } => {
+                write!(f, "expected {expected} global outputs, found {actual}")
+            }
+            Self::NoOwner => write!(f, "local edge has no node to own it"),
+        }
+    }
+}
+
+impl std::error::Error for ComposeError {}
+
+impl<N, E> Graph<N, E> {
+    /// 将本图的全图输出连接到 `other` 的全图输入，组合为一个图。
+    ///
+    /// `links` 中的 `(i, j)` 表示本图的第 `i` 个全图输出连接到 `other` 的第 `j` 个全图输入，
+    /// 连接后的边使用本图的边信息。组合后的全图输入是本图的全图输入和 `other` 未连接的全图输入；
+    /// 全图输出是本图未连接的全图输出和 `other` 的全图输出。
+    ///
+    /// 返回组合后的图，以及本图和 `other` 的节点、边序号的映射。本图的节点序号不变。
+    pub fn chain(
+        self,
+        other: Self,
+        links: &[(usize, usize)],
+    ) -> Result<(Self, Remap, Remap), ComposeError> {
+        let mut linked_outputs = vec![false; self.topology.global_outputs_len()];
+        let mut linked_inputs = vec![None; other.topology.global_inputs_len()];
+        for &(i, j) in links {
+            *linked_outputs
+                .get_mut(i)
+                .ok_or(ComposeError::NoSuchGlobalOutput(i))? = true;
+            match linked_inputs.get_mut(j) {
+                Some(Some(_)) => return Err(ComposeError::DuplicateLink(j)),
+                Some(slot) => *slot = Some(i),
+                None => return Err(ComposeError::NoSuchGlobalInput(j)),
+            }
+        }
+
+        let (nodes_len, edges_len) = (self.nodes.len(), self.edges.len());
+        let mut loose = Loose::from(self);
+        let other = Loose::from(other);
+        let self_outputs = std::mem::take(&mut loose.global_outputs);
+        let bind = other
+            .global_inputs
+            .iter()
+            .zip(&linked_inputs)
+            .filter_map(|(&e, i)| i.map(|i| (e, self_outputs[i])))
+            .collect();
+        let (other_inputs, other_outputs) =
+            (other.global_inputs.clone(), other.global_outputs.clone());
+        let positions = loose.append(other, &bind);
+
+        loose.global_inputs.extend(
+            other_inputs
+                .iter()
+                .zip(&linked_inputs)
+                .filter(|(_, i)| i.is_none())
+                .map(|(&e, _)| positions[e]),
+        );
+        loose.global_outputs = self_outputs
+            .into_iter()
+            .zip(linked_outputs)
+            .filter(|(_, linked)| !linked)
+            .map(|(e, _)| e)
+            .chain(other_outputs.iter().map(|&e| positions[e]))
+            .collect();
+
+        let (graph, remap) = loose.compact();
+        let (this, other) = remap.split(nodes_len, edges_len, &positions);
+        Ok((graph, this, other))
+    }
+
+    /// 用 `callee` 替换节点，`callee` 的全图输入绑定到节点的入边，全图输出替代节点的出边。
+    ///
+    /// 节点出边的使用者改为使用 `callee` 中对应的边，使用 `callee` 的边信息。
+    /// `callee` 的节点被排在原节点的位置，拓扑序允许时其他节点保持原来的顺序。
+    /// 返回本图和 `callee` 的节点、边序号的映射。
+    pub fn inline(&mut self, node: NodeIdx, callee: Self) -> Result<(Remap, Remap), ComposeError> {
+        let topo = &self.topology;
+        if node.index() >= topo.nodes_len() {
+            return Err(ComposeError::NoSuchNode(node));
+        }
+        let (inputs, outputs) = topo.node(node);
+        let callee_topo = &callee.topology;
+        if callee_topo.global_inputs_len() != inputs.len() {
+            return Err(ComposeError::InputsMismatch {
+                expected: inputs.len(),
+                actual: callee_topo.global_inputs_len(),
+            });
+        }
+        if callee_topo.global_outputs_len() != outputs.len() {
+            return Err(ComposeError::OutputsMismatch {
+                expected: outputs.len(),
+                actual: callee_topo.global_outputs_len(),
+            });
+        }
+        if topo.nodes_len() == 1 && callee_topo.nodes_len() == 0 {
+            // 内联后没有节点，全图输出只能引用全图输入
+            for e in topo.global_outputs() {
+                let e = if outputs.contains(e.0) {
+                    let k = e.0.index() - outputs.start().index();
+                    inputs[callee_topo.global_outputs()[k].0.index()].0
+                } else {
+                    e.0
+                };
+                if e.index() >= topo.global_inputs_len() {
+                    return Err(ComposeError::NoOwner);
+                }
+            }
+        }
+
+        let (nodes_len, edges_len) = (self.nodes.len(), self.edges.len());
+        let bind = callee_topo
+            .global_inputs()
+            .zip(inputs)
+            .map(|(e, input)| (e.index(), input.0.index()))
+            .collect();
+        let mut loose = Loose::from(self.take());
+        loose.nodes[node.index()] = None;
+        let callee = Loose::from(callee);
+        let callee_outputs = callee.global_outputs.clone();
+        let first = loose.nodes.len();
+        let positions = loose.append(callee, &bind);
+        // 内联的节点占据被替换节点的位置
+        for callee_node in loose.nodes[first..].iter_mut().flatten() {
+            callee_node.priority = node.index();
+        }
+        for (output, e) in outputs.zip(callee_outputs) {
+            loose.replace_uses(output.index(), positions[e]);
+            loose.redirects.push((output.index(), positions[e]));
+        }
+
+        let (graph, remap) = loose.compact();
+        *self = graph;
+        Ok(remap.split(nodes_len, edges_len, &positions))
+    }
+}
+
+impl<N, E> Loose<N, E> {
+    /// 追加另一个图的节点和边，`bind` 中的边被绑定到本图已有的边。
+    ///
+    /// 追加的节点的优先级是它在本图中的位置，整理时排在已有节点之后。
+    /// 返回另一个图的每条边在本图中的位置，不修改全图输入和全图输出。
+    fn append(&mut self, other: Self, bind: &HashMap<usize, usize>) -> Vec<usize> {
+        let positions = other
+            .edges
+            .into_iter()
+            .enumerate()
+            .map(|(e, edge)| match bind.get(&e) {
+                Some(&p) => p,
+                None => {
+                    self.edges.push(edge);
+                    self.edges.len() - 1
+                }
+            })
+            .collect::<Vec<_>>();
+        for mut node in other.nodes.into_iter().flatten() {
+            for e in node
+                .locals
+                .iter_mut()
+                .chain(&mut node.inputs)
+                .chain(&mut node.outputs)
+            {
+                *e = positions[*e];
+            }
+            node.priority = self.nodes.len();
+            self.nodes.push(Some(node));
+        }
+        self.named |= other.named;
+        positions
+    }
+}
+
+impl Remap {
+    /// 将组合后的映射拆分为两个图各自的映射，第二个图的节点排在前 `nodes_len` 个节点之后。
+    fn split(self, nodes_len: usize, edges_len: usize, positions: &[usize]) -> (Self, Self) {
+        let second = Self {
+            nodes: self.nodes[nodes_len..].to_vec(),
+            edges: positions.iter().map(|&p| self.edges[p]).collect(),
+        };
+        let mut first = self;
+        first.nodes.truncate(nodes_len);
+        first.edges.truncate(edges_len);
+        (first, second)
+    }
+}
+
+#[test]
+fn test_compose() {
+    use crate::EdgeIdx;
+
+    let pre = "
+        %img: \"u8\" = input
+        %x: \"f32\" = node#0 \"Normalize\"(%img)
+        return %x
+    ";
+    let backbone = "
+        %x: \"any\" = input
+        %y: \"f32\" = node#0 \"Block\"(%x)
+        return %y
+    ";
+    let pre = pre.parse::<Graph<String, String>>().unwrap();
+    let backbone = backbone.parse::<Graph<String, String>>().unwrap();
+    let (mut graph, _, remap) = pre.chain(backbone, &[(0, 0)]).unwrap();
+    assert_eq!(remap.edge(EdgeIdx::new(0)), Some(EdgeIdx::new(1)));
+    assert_eq!(
+        graph.to_string(),
+        "\
+%0: \"u8\" = input
+%1: \"f32\" = node#0 \"Normalize\"(%0)
+%2: \"f32\" = node#1 \"Block\"(%1)
+return %2
+"
+    );
+
+    let block = "
+        %x = input
+        %w: \"w\" = local
+        %t: \"t\" = node#0 \"MatMul\"(%x, %w)
+        %y: \"y\" = node#1 \"Relu\"(%t)
+        return %y
+    ";
+    let block = block.parse::<Graph<String, String>>().unwrap();
+    let (this, callee) = graph.inline(NodeIdx::new(1), block).unwrap();
+    assert_eq!(this.node(NodeIdx::new(1)), None);
+    assert_eq!(this.edge(EdgeIdx::new(2)), Some(EdgeIdx::new(4)));
+    assert_eq!(callee.edge(EdgeIdx::new(0)), Some(EdgeIdx::new(1)));
+    assert_eq!(
+        graph.to_string(),
+        "\
+%0: \"u8\" = input
+%1: \"f32\" = node#0 \"Normalize\"(%0)
+%2: \"w\" = local
+%3: \"t\" = node#1 \"MatMul\"(%1, %2)
+%4: \"y\" = node#2 \"Relu\"(%3)
+return %4
+"
+    );
+
+    assert_eq!(
+        graph.clone().chain(graph.clone(), &[(0, 1)]).unwrap_err(),
+        ComposeError::NoSuchGlobalInput(1)
+    );
+
+    // 与被内联节点无关的节点不会被重新编号
+    let text = "
+        %x = input
+        %a = node#0 \"A\"(%x)
+        %b = node#1 \"B\"(%x)
+        return %a, %b
+    ";
+    let mut graph = text.parse::<Graph<String, String>>().unwrap();
+    let callee = "
+        %x = input
+        %t = node#0 \"T\"(%x)
+        %y = node#1 \"Y\"(%t)
+        return %y
+    ";
+    let callee = callee.parse::<Graph<String, String>>().unwrap();
+    let (this, _) = graph.inline(NodeIdx::new(0), callee).unwrap();
+    assert_eq!(this.node(NodeIdx::new(1)), Some(NodeIdx::new(2)));
+    assert_eq!(graph.nodes, ["T", "Y", "B"]);
+}
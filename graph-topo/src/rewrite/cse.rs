@@ -0,0 +1,110 @@
+use super::{Loose, Remap};
+use crate::Graph;
+use std::{collections::HashMap, hash::Hash};
+
+impl<N, E> Graph<N, E> {
+    /// 合并公共子表达式，返回节点和边序号的映射。
+    ///
+    /// `key` 相等、入边相同且出边数量相同的节点被视为等价，后出现的节点被删除，
+    /// 它的出边的使用者改为使用第一个等价节点的出边。没有出边的节点不会被合并。
+    pub fn eliminate_common_subexpressions<K: Eq + Hash>(
+        &mut self,
+        mut key: impl FnMut(&N) -> K,
+    ) -> Remap {
+        let topo = &self.topology;
+        let mut canonical = (0..topo.calculate_edge_len()).collect::<Vec<_>>();
+        let mut seen = HashMap::new();
+        let mut duplicates = Vec::new();
+        for (i, inputs, outputs) in topo {
+            if outputs.is_empty() {
+                continue;
+            }
+            let inputs = inputs
+                .iter()
+                .map(|e| canonical[e.0.index()])
+                .collect::<Vec<_>>();
+            let k = (key(&self.nodes[i.index()]), inputs, outputs.len());
+            match seen.get(&k) {
+                Some(&first) => {
+                    let (_, first_outputs) = topo.node(first);
+                    for (dup, e) in outputs.zip(first_outputs) {
+                        canonical[dup.index()] = e.index();
+                    }
+                    duplicates.push(i);
+                }
+                None => {
+                    seen.insert(k, i);
+                }
+            }
+        }
+        if duplicates.is_empty() {
+            return Remap::identity(self);
+        }
+
+        let mut loose = Loose::from(self.take());
+        for i in duplicates {
+            let node = loose.nodes[i.index()].take().unwrap();
+            loose
+                .redirects
+                .extend(node.outputs.iter().map(|&e| (e, canonical[e])));
+        }
+        for node in loose.nodes.iter_mut().flatten() {
+            for e in &mut node.inputs {
+                *e = canonical[*e];
+            }
+        }
+        for e in &mut loose.global_outputs {
+            *e = canonical[*e];
+        }
+        let (graph, remap) = loose.compact();
+        *self = graph;
+        remap
+    }
+}
+
+#[test]
+fn test_eliminate_common_subexpressions() {
+    use crate::{EdgeIdx, NodeIdx};
+
+    let text = "
+        %x = input
+        %i: \"0\" = local
+        %s0 = node#0 \"Shape\"(%x)
+        %g0 = node#1 \"Gather\"(%s0, %i)
+        %s1 = node#2 \"Shape\"(%x)
+        %g1 = node#3 \"Gather\"(%s1, %i)
+        %y = node#4 \"Concat\"(%g0, %g1)
+        return %y, %g1
+    ";
+    let mut graph = text.parse::<Graph<String, String>>().unwrap();
+    let remap = graph.eliminate_common_subexpressions(String::clone);
+    assert_eq!(
+        graph.to_string(),
+        "\
+%0 = input
+%1: \"0\" = local
+%2 = node#0 \"Shape\"(%0)
+%3 = node#1 \"Gather\"(%2, %1)
+%4 = node#2 \"Concat\"(%3, %3)
+return %4, %3
+"
+    );
+    let nodes = (0..5).map(|i| remap.node(NodeIdx::new(i)).map(NodeIdx::index));
+    assert_eq!(
+        nodes.collect::<Vec<_>>(),
+        [Some(0), Some(1), None, None, Some(2)]
+    );
+    let edges = (0..7).map(|e| remap.edge(EdgeIdx::new(e)).map(EdgeIdx::index));
+    assert_eq!(
+        edges.collect::<Vec<_>>(),
+        [
+            Some(0),
+            Some(1),
+            Some(2),
+            Some(3),
+            Some(2),
+            Some(3),
+            Some(4)
+        ]
+    );
+}
@@ -0,0 +1,109 @@
+use super::{Loose, Remap};
+use crate::Graph;
+
+impl<N, E> Graph<N, E> {
+    /// 删除所有输出不能到达全图输出的节点，以及不再被引用的边，返回节点和边序号的映射。
+    ///
+    /// 全图输入总是保留，存活节点的相对顺序不变。
+    pub fn eliminate_dead_nodes(&mut self) -> Remap {
+        let topo = &self.topology;
+        let mut live = vec![false; topo.calculate_edge_len()];
+        for e in topo.global_outputs() {
+            live[e.0.index()] = true;
+        }
+        // 局部边直接作为全图输出时，整理会把它交给任一存活的节点，
+        // 只有没有节点的输出是全图输出时，才需要保留它所属的节点
+        let mut produced = false;
+        let mut owner = None;
+        for (i, _, outputs) in topo {
+            produced |= outputs.clone().any(|e| live[e.index()]);
+            let first_local = outputs.start().index() - topo.nodes[i.index()].local_edges_len();
+            if owner.is_none() && (first_local..outputs.start().index()).any(|e| live[e]) {
+                owner = Some(i);
+            }
+        }
+        let keep = if produced { None } else { owner };
+        let mut alive = vec![false; topo.nodes_len()];
+        for (i, inputs, outputs) in topo.traverse().rev() {
+            if keep == Some(i) || outputs.clone().any(|e| live[e.index()]) {
+                alive[i.index()] = true;
+                for input in inputs {
+                    live[input.0.index()] = true;
+                }
+            }
+        }
+
+        let mut loose = Loose::from(self.take());
+        for (node, alive) in loose.nodes.iter_mut().zip(alive) {
+            if !alive {
+                *node = None;
+            }
+        }
+        let (graph, remap) = loose.compact();
+        *self = graph;
+        remap
+    }
+}
+
+#[test]
+fn test_eliminate_dead_nodes() {
+    use crate::{EdgeIdx, NodeIdx};
+
+    let text = "
+        %a: \"a\" = input
+        %b: \"b\" = node#0 \"A\"(%a)
+        %w: \"w\" = local
+        %c: \"c\" = node#1 \"Dead\"(%b, %w)
+        %d: \"d\" = node#2 \"B\"(%b, %w)
+        node#3 \"Print\"(%d)
+        return %d
+    ";
+    let mut graph = text.parse::<Graph<String, String>>().unwrap();
+    let remap = graph.eliminate_dead_nodes();
+    assert_eq!(graph.validate(), Ok(()));
+    assert_eq!(graph.nodes, ["A", "B"]);
+    assert_eq!(graph.edges, ["a", "b", "w", "d"]);
+    assert_eq!(
+        graph.to_string(),
+        "\
+%0: \"a\" = input
+%1: \"b\" = node#0 \"A\"(%0)
+%2: \"w\" = local
+%3: \"d\" = node#1 \"B\"(%1, %2)
+return %3
+"
+    );
+
+    let nodes = (0..4).map(|i| remap.node(NodeIdx::new(i)));
+    let nodes = nodes.map(|i| i.map(NodeIdx::index)).collect::<Vec<_>>();
+    assert_eq!(nodes, [Some(0), None, Some(1), None]);
+    let edges = (0..5).map(|e| remap.edge(EdgeIdx::new(e)));
+    let edges = edges.map(|e| e.map(EdgeIdx::index)).collect::<Vec<_>>();
+    assert_eq!(edges, [Some(0), Some(1), Some(2), None, Some(3)]);
+
+    // 与死节点的唯一联系是同时作为全图输出的局部边
+    let text = "
+        %x: \"x\" = input
+        %w: \"w\" = local
+        %d: \"d\" = node#0 \"Dead\"(%x, %w)
+        %y: \"y\" = node#1 \"Live\"(%x)
+        return %y, %w
+    ";
+    let mut graph = text.parse::<Graph<String, String>>().unwrap();
+    graph.eliminate_dead_nodes();
+    assert_eq!(graph.validate(), Ok(()));
+    assert_eq!(graph.nodes, ["Live"]);
+    assert_eq!(graph.edges, ["x", "w", "y"]);
+
+    // 没有其他节点存活时，保留局部边所属的节点
+    let text = "
+        %x: \"x\" = input
+        %w: \"w\" = local
+        %d: \"d\" = node#0 \"Owner\"(%x, %w)
+        return %w
+    ";
+    let mut graph = text.parse::<Graph<String, String>>().unwrap();
+    graph.eliminate_dead_nodes();
+    assert_eq!(graph.validate(), Ok(()));
+    assert_eq!(graph.nodes, ["Owner"]);
+}
@@ -0,0 +1,330 @@
+use super::{Loose, Remap};
+use crate::{EdgeIdx, Graph, NodeIdx};
+use std::fmt;
+
+/// 修改图结构时发现的错误，出错时图不会被修改。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum EditError {
+    /// 节点不存在。
+    NoSuchNode(NodeIdx),
+    /// 边不存在。
+    NoSuchEdge(EdgeIdx),
+    /// 节点没有这个位置的入边。
+    NoSuchInput {
+        /// 节点序号。
+        node: NodeIdx,
+        /// 入边的位置。
+        slot: usize,
+    },
+    /// 删除节点时，除第一条出边外的出边仍被使用。
+    OutputInUse(EdgeIdx),
+    /// 删除最后一个节点后，全图输出引用的局部边没有节点可以归属。
+    NoOwner(EdgeIdx),
+    /// 子图为空或不连通。
+    Disconnected,
+    /// 子图之外的这个节点既依赖子图又被子图依赖，替换后将成环。
+    Cycle(NodeIdx),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSuchNode(node) => write!(f, "node {node} does not exist"),
+            Self::NoSuchEdge(edge) => write!(f, "edge {edge} does not exist"),
+            Self::NoSuchInput { node, slot } => write!(f, "node {node} has no input {slot}"),
+            Self::OutputInUse(edge) => write!(f, "output edge {edge} is still in use"),
+            Self::NoOwner(edge) => write!(f, "local edge {edge} has no node to own it"),
+            Self::Disconnected => write!(f, "subgraph is empty or disconnected"),
+            Self::Cycle(node) => {
+                write!(f, "node {node} depends on and feeds the subgraph")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+impl<N, E> Graph<N, E> {
+    /// 在边和它的所有使用者之间插入一个节点，返回新节点的序号和节点、边序号的映射。
+    ///
+    /// 新节点以 `edge` 为唯一的入边，产生一条信息为 `output` 的新边，
+    /// 原来使用 `edge` 的节点和全图输出改为使用新边。
+    /// 新节点被排在第一个使用者之前，只有之后的节点会被重新编号。
+    pub fn insert_node(
+        &mut self,
+        edge: EdgeIdx,
+        info: N,
+        output: E,
+    ) -> Result<(NodeIdx, Remap), EditError> {
+        if edge.index() >= self.edges.len() {
+            return Err(EditError::NoSuchEdge(edge));
+        }
+
+        // 新节点占据第一个使用者的位置，没有使用者时排在最后
+        let first_use = self
+            .topology
+            .traverse()
+            .find(|(_, inputs, _)| inputs.iter().any(|e| e.0 == edge))
+            .map_or(self.nodes.len(), |(i, _, _)| i.index());
+        let mut loose = Loose::from(self.take());
+        let new_edge = loose.push_edge(output);
+        loose.replace_uses(edge.index(), new_edge);
+        let i = loose.push_node(info, first_use, vec![edge.index()], vec![new_edge]);
+        let (graph, remap) = loose.compact();
+        *self = graph;
+        Ok((remap.node(NodeIdx::new(i)).unwrap(), remap))
+    }
+
+    /// 删除一个节点，它的第一条出边的使用者改为使用它的第 `slot` 个入边，返回节点和边序号的映射。
+    ///
+    /// 节点的其他出边必须没有被使用。
+    pub fn remove_node(&mut self, node: NodeIdx, slot: usize) -> Result<Remap, EditError> {
+        let topo = &self.topology;
+        if node.index() >= topo.nodes_len() {
+            return Err(EditError::NoSuchNode(node));
+        }
+        let (inputs, mut outputs) = topo.node(node);
+        let forward = inputs
+            .get(slot)
+            .ok_or(EditError::NoSuchInput { node, slot })?
+            .0;
+        let first = outputs.next();
+        if let Some(e) = outputs.find(|e| topo.connections.iter().any(|c| c.0 == *e)) {
+            return Err(EditError::OutputInUse(e));
+        }
+        if topo.nodes_len() == 1 {
+            for e in topo.global_outputs() {
+                let e = if Some(e.0) == first { forward } else { e.0 };
+                if e.index() >= topo.global_inputs_len() {
+                    return Err(EditError::NoOwner(e));
+                }
+            }
+        }
+
+        let mut loose = Loose::from(self.take());
+        loose.nodes[node.index()] = None;
+        if let Some(first) = first {
+            loose.replace_uses(first.index(), forward.index());
+            loose.redirects.push((first.index(), forward.index()));
+        }
+        let (graph, remap) = loose.compact();
+        *self = graph;
+        Ok(remap)
+    }
+
+    /// 用一个新节点替换连通的子图，返回新节点的序号和节点、边序号的映射。
+    ///
+    /// 新节点的入边是子图使用的、不由子图产生的边，按第一次使用的顺序排列；
+    /// 新节点的出边是子图产生的、被子图之外使用的边，按边序号排列，保留原来的边信息。
+    /// 子图内部的边被删除。新节点被排在子图中序号最小的节点的位置，拓扑序允许时其他节点保持原来的顺序。
+    pub fn replace_subgraph(
+        &mut self,
+        nodes: &[NodeIdx],
+        info: N,
+    ) -> Result<(NodeIdx, Remap), EditError> {
+        let topo = &self.topology;
+        let mut in_set = vec![false; topo.nodes_len()];
+        for &i in nodes {
+            *in_set.get_mut(i.index()).ok_or(EditError::NoSuchNode(i))? = true;
+        }
+        let mut producer = vec![None; self.edges.len()];
+        for (i, _, outputs) in topo {
+            for e in outputs {
+                producer[e.index()] = Some(i.index());
+            }
+        }
+
+        // 检查连通性
+        let mut parent = (0..topo.nodes_len()).collect::<Vec<_>>();
+        fn find(parent: &mut [usize], i: usize) -> usize {
+            if parent[i] != i {
+                parent[i] = find(parent, parent[i]);
+            }
+            parent[i]
+        }
+        for (i, inputs, _) in topo {
+            if in_set[i.index()] {
+                for input in inputs {
+                    if let Some(p) = producer[input.0.index()].filter(|&p| in_set[p]) {
+                        let (a, b) = (find(&mut parent, i.index()), find(&mut parent, p));
+                        parent[a] = b;
+                    }
+                }
+            }
+        }
+        let mut roots = (0..topo.nodes_len())
+            .filter(|&i| in_set[i])
+            .map(|i| find(&mut parent, i))
+            .collect::<Vec<_>>();
+        roots.sort_unstable();
+        roots.dedup();
+        if roots.len() != 1 {
+            return Err(EditError::Disconnected);
+        }
+
+        // 检查子图之外没有从子图出发又回到子图的路径
+        let mut tainted = vec![false; topo.nodes_len()];
+        for (i, inputs, _) in topo {
+            let mut producers = inputs.iter().filter_map(|e| producer[e.0.index()]);
+            if in_set[i.index()] {
+                if let Some(p) = producers.find(|&p| tainted[p]) {
+                    return Err(EditError::Cycle(NodeIdx::new(p)));
+                }
+            } else {
+                tainted[i.index()] = producers.any(|p| in_set[p] || tainted[p]);
+            }
+        }
+
+        let mut new_inputs = Vec::new();
+        let mut used_outside = vec![false; self.edges.len()];
+        for (i, inputs, _) in topo {
+            for e in inputs.iter().map(|e| e.0.index()) {
+                if !in_set[i.index()] {
+                    used_outside[e] = true;
+                } else if producer[e].is_none_or(|p| !in_set[p]) && !new_inputs.contains(&e) {
+                    new_inputs.push(e);
+                }
+            }
+        }
+        for e in topo.global_outputs() {
+            used_outside[e.0.index()] = true;
+        }
+        let new_outputs = (0..self.edges.len())
+            .filter(|&e| used_outside[e] && producer[e].is_some_and(|p| in_set[p]))
+            .collect::<Vec<_>>();
+
+        let mut loose = Loose::from(self.take());
+        for (node, in_set) in loose.nodes.iter_mut().zip(in_set) {
+            if in_set {
+                *node = None;
+            }
+        }
+        let first = nodes.iter().map(|i| i.index()).min().unwrap();
+        let i = loose.push_node(info, first, new_inputs, new_outputs);
+        let (graph, remap) = loose.compact();
+        *self = graph;
+        Ok((remap.node(NodeIdx::new(i)).unwrap(), remap))
+    }
+}
+
+impl<N, E> Loose<N, E> {
+    /// 将所有节点和全图输出对边 `from` 的使用改为 `to`。
+    pub(super) fn replace_uses(&mut self, from: usize, to: usize) {
+        let uses = self
+            .nodes
+            .iter_mut()
+            .flatten()
+            .flat_map(|node| &mut node.inputs)
+            .chain(&mut self.global_outputs);
+        for e in uses.filter(|e| **e == from) {
+            *e = to;
+        }
+    }
+}
+
+#[test]
+fn test_edit() {
+    let text = "
+        %x: \"x\" = input
+        %a: \"a\" = node#0 \"Relu\"(%x)
+        %b: \"b\" = node#1 \"Identity\"(%a)
+        %c: \"c\" = node#2 \"Exp\"(%b)
+        %d: \"d\" = node#3 \"Log\"(%c)
+        return %d, %a
+    ";
+    let mut graph = text.parse::<Graph<String, String>>().unwrap();
+
+    let remap = graph.remove_node(NodeIdx::new(1), 0).unwrap();
+    assert_eq!(remap.edge(EdgeIdx::new(2)), Some(EdgeIdx::new(1)));
+    assert_eq!(remap.node(NodeIdx::new(2)), Some(NodeIdx::new(1)));
+
+    let (node, _) = graph
+        .insert_node(EdgeIdx::new(1), "Cast".to_string(), "y".to_string())
+        .unwrap();
+    assert_eq!(node, NodeIdx::new(1));
+    assert_eq!(
+        graph.to_string(),
+        "\
+%0: \"x\" = input
+%1: \"a\" = node#0 \"Relu\"(%0)
+%2: \"y\" = node#1 \"Cast\"(%1)
+%3: \"c\" = node#2 \"Exp\"(%2)
+%4: \"d\" = node#3 \"Log\"(%3)
+return %4, %2
+"
+    );
+
+    assert_eq!(
+        graph.replace_subgraph(&[NodeIdx::new(0), NodeIdx::new(2)], "Fused".to_string()),
+        Err(EditError::Disconnected)
+    );
+    let nodes = [1, 2, 3].map(NodeIdx::new);
+    let (node, remap) = graph.replace_subgraph(&nodes, "Fused".to_string()).unwrap();
+    assert_eq!(node, NodeIdx::new(1));
+    assert_eq!(remap.edge(EdgeIdx::new(3)), None);
+    assert_eq!(
+        graph.to_string(),
+        "\
+%0: \"x\" = input
+%1: \"a\" = node#0 \"Relu\"(%0)
+%2: \"y\", %3: \"d\" = node#1 \"Fused\"(%1)
+return %3, %2
+"
+    );
+}
+
+#[test]
+fn test_edit_keeps_order() {
+    let text = "
+        %x = input
+        %a = node#0 \"A\"(%x)
+        %b = node#1 \"B\"(%a)
+        %c = node#2 \"C\"(%x)
+        return %b, %c
+    ";
+    let mut graph = text.parse::<Graph<String, ()>>().unwrap();
+    let (node, remap) = graph
+        .insert_node(EdgeIdx::new(1), "New".to_string(), ())
+        .unwrap();
+    // 新节点排在第一个使用者之前，它之前的节点不变
+    assert_eq!(node, NodeIdx::new(1));
+    assert_eq!(remap.node(NodeIdx::new(0)), Some(NodeIdx::new(0)));
+    assert_eq!(graph.nodes, ["A", "New", "B", "C"]);
+
+    let text = "
+        %x = input
+        %a = node#0 \"A\"(%x)
+        %b = node#1 \"B\"(%a)
+        %c = node#2 \"C\"(%x)
+        %d = node#3 \"D\"(%b, %c)
+        return %d
+    ";
+    let mut graph = text.parse::<Graph<String, ()>>().unwrap();
+    let (node, remap) = graph
+        .replace_subgraph(&[NodeIdx::new(1)], "F".to_string())
+        .unwrap();
+    // 替换节点的序号不变，其他节点也不需要重新编号
+    assert_eq!(node, NodeIdx::new(1));
+    for i in [0, 2, 3].map(NodeIdx::new) {
+        assert_eq!(remap.node(i), Some(i));
+    }
+    assert_eq!(graph.nodes, ["A", "F", "C", "D"]);
+}
+
+#[test]
+fn test_replace_subgraph_cycle() {
+    let text = "
+        %x = input
+        %a = node#0 \"A\"(%x)
+        %b = node#1 \"B\"(%a)
+        %c = node#2 \"C\"(%a, %b)
+        return %c
+    ";
+    let mut graph = text.parse::<Graph<String, ()>>().unwrap();
+    let nodes = [0, 2].map(NodeIdx::new);
+    assert_eq!(
+        graph.replace_subgraph(&nodes, "Fused".to_string()),
+        Err(EditError::Cycle(NodeIdx::new(1)))
+    );
+    assert_eq!(graph.nodes, ["A", "B", "C"]);
+}
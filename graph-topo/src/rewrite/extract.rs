@@ -0,0 +1,200 @@
+use super::{Loose, LooseEdge, LooseNode};
+use crate::{EdgeIdx, EditError, Graph, NodeIdx};
+
+/// 新图中的节点和边在原图中的序号。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Origins {
+    nodes: Vec<NodeIdx>,
+    edges: Vec<EdgeIdx>,
+}
+
+impl Origins {
+    /// 新图中的节点在原图中的序号。
+    #[inline]
+    pub fn node(&self, i: NodeIdx) -> NodeIdx {
+        self.nodes[i.index()]
+    }
+
+    /// 新图中的边在原图中的序号。
+    #[inline]
+    pub fn edge(&self, e: EdgeIdx) -> EdgeIdx {
+        self.edges[e.index()]
+    }
+}
+
+impl<N: Clone, E: Clone> Graph<N, E> {
+    /// 将节点集合提取为独立的图，返回新图和新图中节点、边在原图中的序号。
+    ///
+    /// 节点使用的、由集合外的节点产生的边和原图的全图输入成为新图的全图输入，按边序号排列；
+    /// 局部边仍是局部边。集合中的节点产生或拥有的边被集合外使用或是原图的全图输出时，
+    /// 成为新图的全图输出，按边序号排列。
+    ///
+    /// 节点序号超出范围时返回 [`EditError::NoSuchNode`]。
+    pub fn extract(&self, nodes: &[NodeIdx]) -> Result<(Self, Origins), EditError> {
+        let topo = &self.topology;
+        let mut in_set = vec![false; topo.nodes_len()];
+        for &i in nodes {
+            *in_set.get_mut(i.index()).ok_or(EditError::NoSuchNode(i))? = true;
+        }
+        let mut producer = vec![None; self.edges.len()];
+        for (i, _, outputs) in topo {
+            for e in outputs {
+                producer[e.index()] = Some(i.index());
+            }
+        }
+        let is_local = |e: usize| e >= topo.global_inputs_len() && producer[e].is_none();
+
+        let mut loose = Loose {
+            global_inputs: Vec::new(),
+            global_outputs: Vec::new(),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            redirects: Vec::new(),
+            named: self.names.is_some(),
+        };
+        let mut positions = vec![None; self.edges.len()];
+        let mut origins = Vec::new();
+        let mut add_edge = |loose: &mut Loose<N, E>, e: usize| {
+            *positions[e].get_or_insert_with(|| {
+                loose.edges.push(Some(LooseEdge {
+                    info: self.edges[e].clone(),
+                    name: self.edge_name(EdgeIdx::new(e)).unwrap_or("").to_string(),
+                }));
+                origins.push(EdgeIdx::new(e));
+                loose.edges.len() - 1
+            })
+        };
+
+        let mut incoming = topo
+            .traverse()
+            .filter(|(i, _, _)| in_set[i.index()])
+            .flat_map(|(_, inputs, _)| inputs)
+            .map(|e| e.0.index())
+            .filter(|&e| !is_local(e) && producer[e].is_none_or(|p| !in_set[p]))
+            .collect::<Vec<_>>();
+        incoming.sort_unstable();
+        incoming.dedup();
+        loose.global_inputs = incoming.iter().map(|&e| add_edge(&mut loose, e)).collect();
+
+        // 集合中的节点拥有的局部边和产生的出边
+        let mut owned = vec![false; self.edges.len()];
+        let mut node_origins = Vec::new();
+        for (i, inputs, outputs) in topo {
+            if !in_set[i.index()] {
+                continue;
+            }
+            let first_local = outputs.start().index() - topo.nodes[i.index()].local_edges_len();
+            owned[first_local..outputs.end().index()].fill(true);
+            let node = LooseNode {
+                info: self.nodes[i.index()].clone(),
+                priority: loose.nodes.len(),
+                name: self.node_name(i).unwrap_or("").to_string(),
+                locals: (first_local..outputs.start().index())
+                    .map(|e| add_edge(&mut loose, e))
+                    .collect(),
+                inputs: inputs
+                    .iter()
+                    .map(|e| add_edge(&mut loose, e.0.index()))
+                    .collect(),
+                outputs: outputs.map(|e| add_edge(&mut loose, e.index())).collect(),
+            };
+            loose.nodes.push(Some(node));
+            node_origins.push(i);
+        }
+
+        let mut used_outside = vec![false; self.edges.len()];
+        for (i, inputs, _) in topo {
+            if !in_set[i.index()] {
+                for e in inputs {
+                    used_outside[e.0.index()] = true;
+                }
+            }
+        }
+        for e in topo.global_outputs() {
+            used_outside[e.0.index()] = true;
+        }
+        loose.global_outputs = (0..self.edges.len())
+            .filter(|&e| owned[e] && used_outside[e])
+            .filter_map(|e| positions[e])
+            .collect();
+
+        let (graph, remap) = loose.compact();
+        let mut nodes = vec![NodeIdx::new(0); graph.nodes.len()];
+        for (p, origin) in node_origins.into_iter().enumerate() {
+            if let Some(i) = remap.node(NodeIdx::new(p)) {
+                nodes[i.index()] = origin;
+            }
+        }
+        let mut edges = vec![EdgeIdx::new(0); graph.edges.len()];
+        for (p, origin) in origins.into_iter().enumerate() {
+            if let Some(e) = remap.edge(EdgeIdx::new(p)) {
+                edges[e.index()] = origin;
+            }
+        }
+        Ok((graph, Origins { nodes, edges }))
+    }
+}
+
+#[test]
+fn test_extract() {
+    let text = "
+        %x: \"x\" = input
+        %a: \"a\" = node#0 \"A\"(%x)
+        %w: \"w\" = local
+        %b: \"b\", %t: \"t\" = node#1 \"B\"(%a, %w)
+        %c: \"c\" = node#2 \"C\"(%b, %x)
+        %d: \"d\" = node#3 \"D\"(%c, %t)
+        return %d, %b
+    ";
+    let graph = text.parse::<Graph<String, String>>().unwrap();
+    let (sub, origins) = graph.extract(&[NodeIdx::new(1), NodeIdx::new(2)]).unwrap();
+    assert_eq!(
+        sub.to_string(),
+        "\
+%0: \"x\" = input
+%1: \"a\" = input
+%2: \"w\" = local
+%3: \"b\", %4: \"t\" = node#0 \"B\"(%1, %2)
+%5: \"c\" = node#1 \"C\"(%3, %0)
+return %3, %4, %5
+"
+    );
+    let nodes = (0..2).map(|i| origins.node(NodeIdx::new(i)).index());
+    assert_eq!(nodes.collect::<Vec<_>>(), [1, 2]);
+    let edges = (0..6).map(|e| origins.edge(EdgeIdx::new(e)).index());
+    assert_eq!(edges.collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5]);
+    assert_eq!(
+        graph.extract(&[NodeIdx::new(4)]).unwrap_err(),
+        EditError::NoSuchNode(NodeIdx::new(4))
+    );
+
+    // 集合外的节点拥有的局部边被复制为局部边，但不是新图的全图输出
+    let text = "
+        %x: \"x\" = input
+        %w: \"w\" = local
+        %a: \"a\" = node#0 \"A\"(%x, %w)
+        %b: \"b\" = node#1 \"B\"(%a, %w)
+        return %b
+    ";
+    let graph = text.parse::<Graph<String, String>>().unwrap();
+    let (sub, _) = graph.extract(&[NodeIdx::new(1)]).unwrap();
+    assert_eq!(
+        sub.to_string(),
+        "\
+%0: \"a\" = input
+%1: \"w\" = local
+%2: \"b\" = node#0 \"B\"(%0, %1)
+return %2
+"
+    );
+    let (sub, _) = graph.extract(&[NodeIdx::new(0)]).unwrap();
+    assert_eq!(
+        sub.to_string(),
+        "\
+%0: \"x\" = input
+%1: \"w\" = local
+%2: \"a\" = node#0 \"A\"(%0, %1)
+return %1, %2
+"
+    );
+}
@@ -0,0 +1,351 @@
+mod compose;
+mod cse;
+mod dce;
+mod edit;
+mod extract;
+
+use crate::{
+    container::{Names, Node},
+    EdgeIdx, Graph, GraphTopo, NodeIdx, OutputEdge,
+};
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+pub use compose::ComposeError;
+pub use edit::EditError;
+pub use extract::Origins;
+
+/// 图变换前后节点和边序号的映射。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Remap {
+    nodes: Vec<Option<NodeIdx>>,
+    edges: Vec<Option<EdgeIdx>>,
+}
+
+impl Remap {
+    /// 图没有变化时的映射。
+    pub(crate) fn identity<N, E>(graph: &Graph<N, E>) -> Self {
+        Self {
+            nodes: (0..graph.nodes.len())
+                .map(|i| Some(NodeIdx::new(i)))
+                .collect(),
+            edges: (0..graph.edges.len())
+                .map(|e| Some(EdgeIdx::new(e)))
+                .collect(),
+        }
+    }
+
+    /// 节点在变换后的序号，节点被删除时返回 `None`。
+    #[inline]
+    pub fn node(&self, i: NodeIdx) -> Option<NodeIdx> {
+        self.nodes.get(i.index()).copied().flatten()
+    }
+
+    /// 边在变换后的序号，边被删除时返回 `None`。
+    ///
+    /// 被合并或替换的边映射到替代它的边。
+    #[inline]
+    pub fn edge(&self, e: EdgeIdx) -> Option<EdgeIdx> {
+        self.edges.get(e.index()).copied().flatten()
+    }
+}
+
+/// 松散的图表示，节点和边可以任意增删，最后整理为紧凑的图。
+///
+/// 节点和边的位置是它们的标识，从图转换而来时与原来的序号相同。
+/// 整理时按拓扑序重排节点，拓扑序允许时按节点的优先级排列，优先级相同时位置小的优先。
+/// 从图转换而来的节点的优先级就是原来的序号，因此没有变化的部分保持原来的相对顺序。
+pub(crate) struct Loose<N, E> {
+    /// 全图输入边。
+    pub global_inputs: Vec<usize>,
+    /// 全图输出边。
+    pub global_outputs: Vec<usize>,
+    /// 节点，`None` 表示已删除。
+    pub nodes: Vec<Option<LooseNode<N>>>,
+    /// 边，`None` 表示已删除，已删除的边不能再被引用。
+    pub edges: Vec<Option<LooseEdge<E>>>,
+    /// 被合并或替换的边映射到替代它的边。
+    pub redirects: Vec<(usize, usize)>,
+    /// 是否保留名字表。
+    pub named: bool,
+}
+
+/// 松散表示中的节点。
+pub(crate) struct LooseNode<N> {
+    pub info: N,
+    /// 整理时的排序优先级，越小越靠前。
+    pub priority: usize,
+    pub name: String,
+    pub locals: Vec<usize>,
+    pub inputs: Vec<usize>,
+    pub outputs: Vec<usize>,
+}
+
+/// 松散表示中的边。
+pub(crate) struct LooseEdge<E> {
+    pub info: E,
+    pub name: String,
+}
+
+impl<N, E> From<Graph<N, E>> for Loose<N, E> {
+    fn from(graph: Graph<N, E>) -> Self {
+        let Graph {
+            topology,
+            nodes,
+            edges,
+            names,
+        } = graph;
+        let named = names.is_some();
+        let Names {
+            nodes: node_names,
+            edges: edge_names,
+        } = names.unwrap_or_default();
+        let mut node_names = node_names.into_iter();
+        let mut edge_names = edge_names.into_iter();
+
+        let nodes = topology
+            .traverse()
+            .zip(nodes)
+            .map(|((i, inputs, outputs), info)| {
+                let first_output = outputs.start().index();
+                let locals =
+                    first_output - topology.nodes[i.index()].local_edges_len()..first_output;
+                Some(LooseNode {
+                    info,
+                    priority: i.index(),
+                    name: node_names.next().unwrap_or_default(),
+                    locals: locals.collect(),
+                    inputs: inputs.iter().map(|e| e.0.index()).collect(),
+                    outputs: outputs.map(EdgeIdx::index).collect(),
+                })
+            })
+            .collect();
+        let edges = edges
+            .into_iter()
+            .map(|info| {
+                Some(LooseEdge {
+                    info,
+                    name: edge_names.next().unwrap_or_default(),
+                })
+            })
+            .collect();
+        Self {
+            global_inputs: topology.global_inputs().map(EdgeIdx::index).collect(),
+            global_outputs: topology
+                .global_outputs()
+                .iter()
+                .map(|e| e.0.index())
+                .collect(),
+            nodes,
+            edges,
+            redirects: Vec::new(),
+            named,
+        }
+    }
+}
+
+impl<N, E> Loose<N, E> {
+    /// 添加一条边，返回它的位置。
+    pub fn push_edge(&mut self, info: E) -> usize {
+        self.edges.push(Some(LooseEdge {
+            info,
+            name: String::new(),
+        }));
+        self.edges.len() - 1
+    }
+
+    /// 添加一个没有局部边的节点，返回它的位置。
+    ///
+    /// 新节点以 `priority` 参与整理时的排序，优先级相同时排在已有节点之后。
+    pub fn push_node(
+        &mut self,
+        info: N,
+        priority: usize,
+        inputs: Vec<usize>,
+        outputs: Vec<usize>,
+    ) -> usize {
+        self.nodes.push(Some(LooseNode {
+            info,
+            priority,
+            name: String::new(),
+            locals: Vec::new(),
+            inputs,
+            outputs,
+        }));
+        self.nodes.len() - 1
+    }
+
+    /// 整理为紧凑的图。
+    ///
+    /// 全图输入、存活节点的出边和被引用的边会被保留，其他边被删除。
+    /// 没有生产者的边是局部边，优先归属原来的节点，否则归属第一个使用它的节点，
+    /// 仅被全图输出引用的局部边归属最后一个节点。
+    ///
+    /// 调用者需要保证节点之间无环，并且有节点可以拥有局部边。
+    pub fn compact(mut self) -> (Graph<N, E>, Remap) {
+        let edges_len = self.edges.len();
+
+        // 按拓扑序排列存活的节点，同时可排时优先级小的优先，其次位置小的优先
+        let mut producer = vec![None; edges_len];
+        for (i, node) in self.nodes.iter().enumerate() {
+            for &e in node.iter().flat_map(|n| &n.outputs) {
+                producer[e] = Some(i);
+            }
+        }
+        let mut indegree = vec![0usize; self.nodes.len()];
+        let mut successors = vec![Vec::new(); self.nodes.len()];
+        for (i, node) in self.nodes.iter().enumerate() {
+            for &e in node.iter().flat_map(|n| &n.inputs) {
+                if let Some(p) = producer[e] {
+                    indegree[i] += 1;
+                    successors[p].push(i);
+                }
+            }
+        }
+        let mut heap = (0..self.nodes.len())
+            .filter(|&i| indegree[i] == 0)
+            .filter_map(|i| self.nodes[i].as_ref().map(|n| Reverse((n.priority, i))))
+            .collect::<BinaryHeap<_>>();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(Reverse((_, i))) = heap.pop() {
+            order.push(i);
+            for &j in &successors[i] {
+                indegree[j] -= 1;
+                if indegree[j] == 0 {
+                    heap.push(Reverse((self.nodes[j].as_ref().unwrap().priority, j)));
+                }
+            }
+        }
+        let alive = self.nodes.iter().filter(|n| n.is_some()).count();
+        assert_eq!(order.len(), alive, "nodes form a cycle");
+
+        // 确定局部边的归属
+        let mut is_global_input = vec![false; edges_len];
+        for &e in &self.global_inputs {
+            is_global_input[e] = true;
+        }
+        let is_local = |e: usize| !is_global_input[e] && producer[e].is_none();
+        let mut owner = vec![None::<usize>; edges_len];
+        for (rank, &i) in order.iter().enumerate() {
+            let node = self.nodes[i].as_ref().unwrap();
+            for &e in &node.locals {
+                owner[e] = Some(rank);
+            }
+        }
+        let mut referenced = vec![false; edges_len];
+        for (rank, &i) in order.iter().enumerate() {
+            for &e in &self.nodes[i].as_ref().unwrap().inputs {
+                referenced[e] = true;
+                if is_local(e) {
+                    owner[e] = Some(owner[e].map_or(rank, |r| r.min(rank)));
+                }
+            }
+        }
+        for &e in &self.global_outputs {
+            referenced[e] = true;
+            if is_local(e) && owner[e].is_none() {
+                owner[e] = Some(
+                    order
+                        .len()
+                        .checked_sub(1)
+                        .expect("no node to own local edge"),
+                );
+            }
+        }
+        let mut locals = vec![Vec::new(); order.len()];
+        let mut placed = vec![false; edges_len];
+        for (rank, &i) in order.iter().enumerate() {
+            for &e in &self.nodes[i].as_ref().unwrap().locals {
+                if referenced[e] && owner[e] == Some(rank) && !placed[e] {
+                    placed[e] = true;
+                    locals[rank].push(e);
+                }
+            }
+        }
+        for e in 0..edges_len {
+            if let (true, false, Some(rank)) = (referenced[e], placed[e], owner[e]) {
+                if is_local(e) {
+                    locals[rank].push(e);
+                }
+            }
+        }
+
+        // 重新编号
+        let mut edge_map = vec![None; edges_len];
+        let mut edges = Vec::with_capacity(edges_len);
+        let mut edge_names = Vec::with_capacity(edges_len);
+        let mut take_edge = |e: usize, edge_map: &mut [Option<EdgeIdx>]| {
+            let LooseEdge { info, name } = self.edges[e].take().expect("edge is removed");
+            edge_map[e] = Some(EdgeIdx::new(edges.len()));
+            edges.push(info);
+            edge_names.push(name);
+        };
+        for &e in &self.global_inputs {
+            take_edge(e, &mut edge_map);
+        }
+        let mut node_map = vec![None; self.nodes.len()];
+        let mut live_nodes = Vec::with_capacity(order.len());
+        for (rank, &i) in order.iter().enumerate() {
+            let node = self.nodes[i].take().unwrap();
+            for &e in &locals[rank] {
+                take_edge(e, &mut edge_map);
+            }
+            for &e in &node.outputs {
+                take_edge(e, &mut edge_map);
+            }
+            node_map[i] = Some(NodeIdx::new(rank));
+            live_nodes.push((node, locals[rank].len()));
+        }
+        for &(from, to) in &self.redirects {
+            edge_map[from] = edge_map[to];
+        }
+
+        let map = |e: &usize| OutputEdge(edge_map[*e].expect("edge is removed"));
+        let mut topo_nodes = Vec::with_capacity(live_nodes.len());
+        let mut connections = Vec::new();
+        let mut nodes = Vec::with_capacity(live_nodes.len());
+        let mut node_names = Vec::with_capacity(live_nodes.len());
+        for (node, locals_len) in live_nodes {
+            topo_nodes.push(Node::new(locals_len, node.inputs.len(), node.outputs.len()));
+            connections.extend(node.inputs.iter().map(map));
+            nodes.push(node.info);
+            node_names.push(node.name);
+        }
+        connections.extend(self.global_outputs.iter().map(map));
+
+        let graph = Graph {
+            topology: GraphTopo {
+                global_inputs_len: self.global_inputs.len(),
+                global_outputs_len: self.global_outputs.len(),
+                nodes: topo_nodes,
+                connections,
+                offsets: Default::default(),
+            },
+            nodes,
+            edges,
+            names: self.named.then_some(Names {
+                nodes: node_names,
+                edges: edge_names,
+            }),
+        };
+        debug_assert_eq!(graph.validate(), Ok(()));
+        (
+            graph,
+            Remap {
+                nodes: node_map,
+                edges: edge_map,
+            },
+        )
+    }
+}
+
+impl<N, E> Graph<N, E> {
+    /// 取出图，留下一个空图。
+    pub(crate) fn take(&mut self) -> Self {
+        Self {
+            topology: std::mem::take(&mut self.topology),
+            nodes: std::mem::take(&mut self.nodes),
+            edges: std::mem::take(&mut self.edges),
+            names: self.names.take(),
+        }
+    }
+}
@@ -1,10 +1,6 @@
-﻿use super::GraphTopo;
+﻿use crate::{EdgeIdx, GraphTopo, NodeIdx};
 use std::collections::HashSet;
 
-pub(super) type NodeIdx = usize;
-pub(super) type EdgeIdx = usize;
-pub(super) const EXTERNAL: NodeIdx = NodeIdx::MAX;
-
 #[derive(Clone, Debug)]
 pub(super) struct Internal {
     pub(super) global_inputs: Vec<EdgeIdx>,
@@ -20,22 +16,17 @@ pub(super) struct SeacherNode {
     pub(super) outputs: Vec<EdgeIdx>,
     pub(super) predecessors: HashSet<NodeIdx>,
     pub(super) successors: HashSet<NodeIdx>,
+    /// 节点的出边中是否有全图输出边。
+    pub(super) to_global_outputs: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Default, Debug)]
 pub(super) struct SeacherEdge {
-    pub(super) source: NodeIdx,
+    /// 边的源节点，全图输入边和局部边没有源节点。
+    pub(super) source: Option<NodeIdx>,
     pub(super) targets: HashSet<NodeIdx>,
-}
-
-impl Default for SeacherEdge {
-    #[inline]
-    fn default() -> Self {
-        Self {
-            source: EXTERNAL,
-            targets: HashSet::new(),
-        }
-    }
+    /// 边是否是全图输出边。
+    pub(super) is_global_output: bool,
 }
 
 impl Internal {
@@ -43,11 +34,11 @@ impl Internal {
         let nodes_len = graph.nodes.len();
         let global_inputs_len = graph.global_inputs_len;
 
-        let global_inputs = (0..global_inputs_len).collect::<Vec<_>>();
+        let global_inputs = graph.global_inputs().collect::<Vec<_>>();
         let mut global_outputs = Vec::new();
         let mut local_edges = HashSet::new();
         let mut nodes = vec![SeacherNode::default(); nodes_len];
-        let mut edges = Vec::new();
+        let mut edges = Vec::<SeacherEdge>::new();
 
         let mut pass_connections = 0;
 
@@ -55,27 +46,29 @@ impl Internal {
             edges.push(Default::default());
         }
         for (node_idx, node) in graph.nodes.iter().enumerate() {
-            for _ in 0..node.local_edges_len {
-                local_edges.insert(edges.len());
+            for _ in 0..node.local_edges_len() {
+                local_edges.insert(EdgeIdx::new(edges.len()));
                 edges.push(Default::default());
             }
-            for _ in 0..node.outputs_len {
-                nodes[node_idx].outputs.push(edges.len());
+            for _ in 0..node.outputs_len() {
+                nodes[node_idx].outputs.push(EdgeIdx::new(edges.len()));
                 edges.push(SeacherEdge {
-                    source: node_idx,
-                    targets: HashSet::new(),
+                    source: Some(NodeIdx::new(node_idx)),
+                    ..Default::default()
                 })
             }
-            for _ in 0..node.inputs_len {
+            for _ in 0..node.inputs_len() {
                 let edge_idx = graph.connections[pass_connections].0;
-                let edge = &mut edges[edge_idx];
+                let edge = &mut edges[edge_idx.index()];
 
                 nodes[node_idx].inputs.push(edge_idx);
-                edge.targets.insert(node_idx);
+                edge.targets.insert(NodeIdx::new(node_idx));
 
-                if edge.source != EXTERNAL {
-                    nodes[node_idx].predecessors.insert(edge.source);
-                    nodes[edge.source].successors.insert(node_idx);
+                if let Some(source) = edge.source {
+                    nodes[node_idx].predecessors.insert(source);
+                    nodes[source.index()]
+                        .successors
+                        .insert(NodeIdx::new(node_idx));
                 }
 
                 pass_connections += 1;
@@ -83,13 +76,13 @@ impl Internal {
         }
         for ouput in &graph.connections[pass_connections..] {
             let edge_idx = ouput.0;
-            let edge = &mut edges[edge_idx];
+            let edge = &mut edges[edge_idx.index()];
 
             global_outputs.push(edge_idx);
-            edge.targets.insert(EXTERNAL);
+            edge.is_global_output = true;
 
-            if edge.source != EXTERNAL {
-                nodes[edge.source].successors.insert(EXTERNAL);
+            if let Some(source) = edge.source {
+                nodes[source.index()].to_global_outputs = true;
             }
         }
 
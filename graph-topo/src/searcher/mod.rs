@@ -1,7 +1,7 @@
 ﻿mod internal;
 
-use crate::GraphTopo;
-use internal::{Internal, NodeIdx};
+use crate::{EdgeIdx, GraphTopo, NodeIdx};
+use internal::Internal;
 use std::{
     cell::RefCell,
     collections::HashSet,
@@ -82,6 +82,7 @@ impl Clone for Searcher {
 impl From<&GraphTopo> for Searcher {
     #[inline]
     fn from(value: &GraphTopo) -> Self {
+        debug_assert_eq!(value.validate(), Ok(()));
         Self(Rc::new(RefCell::new(Internal::new(value))))
     }
 }
@@ -92,10 +93,10 @@ pub struct Nodes(Weak<RefCell<Internal>>);
 pub struct Edges(Weak<RefCell<Internal>>);
 /// 节点索引器。
 #[derive(Clone)]
-pub struct Node(Weak<RefCell<Internal>>, usize);
+pub struct Node(Weak<RefCell<Internal>>, NodeIdx);
 /// 边索引器。
 #[derive(Clone)]
-pub struct Edge(Weak<RefCell<Internal>>, usize);
+pub struct Edge(Weak<RefCell<Internal>>, EdgeIdx);
 #[derive(Clone)]
 pub struct NodeIter(Weak<RefCell<Internal>>, usize);
 #[derive(Clone)]
@@ -103,10 +104,10 @@ pub struct EdgeIter(Weak<RefCell<Internal>>, usize);
 
 impl Nodes {
     #[inline]
-    pub fn get(&self, idx: usize) -> Node {
+    pub fn get(&self, idx: NodeIdx) -> Node {
         let internal = self.0.upgrade().expect("Graph has been dropped");
         let internal = internal.borrow();
-        assert!(idx < internal.nodes.len());
+        assert!(idx.index() < internal.nodes.len());
         Node(self.0.clone(), idx)
     }
 
@@ -137,10 +138,10 @@ impl IntoIterator for Nodes {
 
 impl Edges {
     #[inline]
-    pub fn get(&self, idx: usize) -> Edge {
+    pub fn get(&self, idx: EdgeIdx) -> Edge {
         let internal = self.0.upgrade().expect("Graph has been dropped");
         let internal = internal.borrow();
-        assert!(idx < internal.edges.len());
+        assert!(idx.index() < internal.edges.len());
         Edge(self.0.clone(), idx)
     }
 
@@ -178,7 +179,7 @@ impl Iterator for NodeIter {
             if self.1 < internal.nodes.len() {
                 let idx = self.1;
                 self.1 += 1;
-                Some(Node(self.0.clone(), idx))
+                Some(Node(self.0.clone(), NodeIdx::new(idx)))
             } else {
                 None
             }
@@ -195,7 +196,7 @@ impl Iterator for EdgeIter {
             if self.1 < internal.edges.len() {
                 let idx = self.1;
                 self.1 += 1;
-                Some(Edge(self.0.clone(), idx))
+                Some(Edge(self.0.clone(), EdgeIdx::new(idx)))
             } else {
                 None
             }
@@ -223,7 +224,7 @@ impl Hash for Node {
 impl Node {
     /// 获取节点序号。
     #[inline]
-    pub const fn index(&self) -> usize {
+    pub const fn index(&self) -> NodeIdx {
         self.1
     }
 
@@ -231,7 +232,7 @@ impl Node {
     pub fn inputs(&self) -> Vec<Edge> {
         let internal = self.0.upgrade().expect("Graph has been dropped");
         let internal = internal.borrow();
-        internal.nodes[self.1]
+        internal.nodes[self.1.index()]
             .inputs
             .iter()
             .map(|i| Edge(self.0.clone(), *i))
@@ -242,7 +243,7 @@ impl Node {
     pub fn outputs(&self) -> Vec<Edge> {
         let internal = self.0.upgrade().expect("Graph has been dropped");
         let internal = internal.borrow();
-        internal.nodes[self.1]
+        internal.nodes[self.1.index()]
             .outputs
             .iter()
             .map(|i| Edge(self.0.clone(), *i))
@@ -253,7 +254,7 @@ impl Node {
     pub fn predecessors(&self) -> HashSet<Node> {
         let internal = self.0.upgrade().expect("Graph has been dropped");
         let internal = internal.borrow();
-        internal.nodes[self.1]
+        internal.nodes[self.1.index()]
             .predecessors
             .iter()
             .map(|i| Node(self.0.clone(), *i))
@@ -261,15 +262,24 @@ impl Node {
     }
 
     /// 获取节点后继。
+    ///
+    /// 全图输出不是节点，不在后继节点中，见 [`Node::to_global_outputs`]。
     pub fn successors(&self) -> HashSet<Node> {
         let internal = self.0.upgrade().expect("Graph has been dropped");
         let internal = internal.borrow();
-        internal.nodes[self.1]
+        internal.nodes[self.1.index()]
             .successors
             .iter()
             .map(|i| Node(self.0.clone(), *i))
             .collect()
     }
+
+    /// 判断节点的出边中是否有全图输出边。
+    pub fn to_global_outputs(&self) -> bool {
+        let internal = self.0.upgrade().expect("Graph has been dropped");
+        let internal = internal.borrow();
+        internal.nodes[self.1.index()].to_global_outputs
+    }
 }
 
 impl PartialEq for Edge {
@@ -292,30 +302,38 @@ impl Hash for Edge {
 impl Edge {
     /// 获取边序号。
     #[inline]
-    pub const fn index(&self) -> usize {
+    pub const fn index(&self) -> EdgeIdx {
         self.1
     }
 
     /// 获取边源节点。
+    ///
+    /// 全图输入边和局部边没有源节点。
     pub fn source(&self) -> Option<Node> {
         let internal = self.0.upgrade().expect("Graph has been dropped");
         let internal = internal.borrow();
-        let idx = internal.edges[self.1].source;
-        if idx == NodeIdx::MAX {
-            None
-        } else {
-            Some(Node(self.0.clone(), idx as _))
-        }
+        internal.edges[self.1.index()]
+            .source
+            .map(|i| Node(self.0.clone(), i))
     }
 
     /// 获取边目标节点。
+    ///
+    /// 全图输出不是节点，不在目标节点中，见 [`Edge::is_global_output`]。
     pub fn targets(&self) -> Vec<Node> {
         let internal = self.0.upgrade().expect("Graph has been dropped");
         let internal = internal.borrow();
-        internal.edges[self.1]
+        internal.edges[self.1.index()]
             .targets
             .iter()
             .map(|i| Node(self.0.clone(), *i))
             .collect()
     }
+
+    /// 判断边是否是全图输出边。
+    pub fn is_global_output(&self) -> bool {
+        let internal = self.0.upgrade().expect("Graph has been dropped");
+        let internal = internal.borrow();
+        internal.edges[self.1.index()].is_global_output
+    }
 }
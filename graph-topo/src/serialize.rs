@@ -0,0 +1,94 @@
+use crate::{container::Node, Graph, GraphTopo, Names, OutputEdge, TopoError};
+use serde::Deserialize;
+
+/// 未检查的图拓扑。
+///
+/// 图拓扑的字段之间存在不变量，因此先反序列化为这个结构，检查通过后再转换。
+#[derive(Deserialize)]
+pub(crate) struct RawGraphTopo {
+    global_inputs_len: usize,
+    global_outputs_len: usize,
+    nodes: Vec<Node>,
+    connections: Vec<OutputEdge>,
+}
+
+impl TryFrom<RawGraphTopo> for GraphTopo {
+    type Error = TopoError;
+
+    fn try_from(value: RawGraphTopo) -> Result<Self, Self::Error> {
+        let ans = Self {
+            global_inputs_len: value.global_inputs_len,
+            global_outputs_len: value.global_outputs_len,
+            nodes: value.nodes,
+            connections: value.connections,
+            offsets: Default::default(),
+        };
+        ans.validate().map(|()| ans)
+    }
+}
+
+/// 未检查的图。
+#[derive(Deserialize)]
+pub(crate) struct RawGraph<N, E> {
+    topology: GraphTopo,
+    nodes: Vec<N>,
+    edges: Vec<E>,
+    names: Option<Names>,
+}
+
+impl<N, E> TryFrom<RawGraph<N, E>> for Graph<N, E> {
+    type Error = TopoError;
+
+    fn try_from(value: RawGraph<N, E>) -> Result<Self, Self::Error> {
+        let ans = Self {
+            topology: value.topology,
+            nodes: value.nodes,
+            edges: value.edges,
+            names: value.names,
+        };
+        ans.validate().map(|()| ans)
+    }
+}
+
+#[test]
+fn test_serde() {
+    use crate::Builder;
+
+    let mut builder = Builder::default();
+    builder.add_global_input("a").unwrap();
+    builder
+        .add_node("A", vec!["a", "b"], vec!["c"], "*0".to_string())
+        .unwrap();
+    builder.mark_global_output("c");
+    builder.set_edge_info("b", "|1".to_string());
+    let graph = builder.build_named().unwrap();
+
+    let json = serde_json::to_string(&graph).unwrap();
+    let de = serde_json::from_str::<Graph<String, String>>(&json).unwrap();
+    assert_eq!(de.topology, graph.topology);
+    assert_eq!(de.nodes, graph.nodes);
+    assert_eq!(de.edges, graph.edges);
+    assert_eq!(de.names, graph.names);
+
+    let json = serde_json::to_string(&graph.topology).unwrap();
+    assert_eq!(
+        serde_json::from_str::<GraphTopo>(&json).unwrap(),
+        graph.topology
+    );
+
+    // 全图输出引用了不存在的边。
+    let json = r#"{
+        "global_inputs_len": 1,
+        "global_outputs_len": 1,
+        "nodes": [{ "local_edges_len": 0, "inputs_len": 1, "outputs_len": 1 }],
+        "connections": [0, 2]
+    }"#;
+    let err = serde_json::from_str::<GraphTopo>(json).unwrap_err();
+    assert!(err.to_string().contains("undefined edge 2"));
+
+    // 边信息的数量与拓扑不一致。
+    let mut graph = graph;
+    graph.edges.pop();
+    let json = serde_json::to_string(&graph).unwrap();
+    assert!(serde_json::from_str::<Graph<String, String>>(&json).is_err());
+}
@@ -0,0 +1,509 @@
+use crate::{container::Node, idx::try_repr, EdgeIdx, Graph, GraphTopo, OutputEdge};
+use std::{collections::HashMap, fmt, str::FromStr};
+
+/// 节点和边信息的文本格式。
+///
+/// 文本中的信息以带引号的字符串表示，格式化结果为空时省略。
+pub trait TextPayload: Sized {
+    /// 将信息格式化为文本。
+    fn fmt_text(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    /// 从 [`TextPayload::fmt_text`] 生成的文本解析信息，文本无效时返回 `None`。
+    fn parse_text(text: &str) -> Option<Self>;
+}
+
+impl TextPayload for () {
+    #[inline]
+    fn fmt_text(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Ok(())
+    }
+
+    #[inline]
+    fn parse_text(text: &str) -> Option<Self> {
+        text.is_empty().then_some(())
+    }
+}
+
+impl TextPayload for String {
+    #[inline]
+    fn fmt_text(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self)
+    }
+
+    #[inline]
+    fn parse_text(text: &str) -> Option<Self> {
+        Some(text.to_string())
+    }
+}
+
+/// 文本格式的图不合法。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseError {
+    /// 语法错误。
+    Syntax {
+        /// 行号，从 1 开始。
+        line: usize,
+    },
+    /// 引用了未定义的边。
+    UndefinedEdge {
+        /// 行号，从 1 开始。
+        line: usize,
+        /// 边的名字。
+        name: String,
+    },
+    /// 重复定义了边。
+    DuplicateEdge {
+        /// 行号，从 1 开始。
+        line: usize,
+        /// 边的名字。
+        name: String,
+    },
+    /// 节点或边的信息无法解析。
+    InvalidPayload {
+        /// 行号，从 1 开始。
+        line: usize,
+    },
+    /// 语句的位置不正确：全图输入必须在所有节点之前，局部边之后必须有节点，`return` 必须在最后。
+    Misplaced {
+        /// 行号，从 1 开始。
+        line: usize,
+    },
+    /// 边或连接的数量超出了可表示的范围。
+    Overflow,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax { line } => write!(f, "line {line}: syntax error"),
+            Self::UndefinedEdge { line, name } => {
+                write!(f, "line {line}: undefined edge %{name}")
+            }
+            Self::DuplicateEdge { line, name } => {
+                write!(f, "line {line}: edge %{name} is defined more than once")
+            }
+            Self::InvalidPayload { line } => write!(f, "line {line}: invalid payload"),
+            Self::Misplaced { line } => write!(f, "line {line}: misplaced statement"),
+            Self::Overflow => write!(f, "edge or connection count overflows"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// 以 SSA 形式打印图，例如：
+///
+/// ```text
+/// %0: "a" = input
+/// %1: "b" = local
+/// %2: "c", %3: "d" = node#0 "Add"(%0, %1)
+/// return %3, %2
+/// ```
+///
+/// 每个节点之前的 `local` 是这个节点的局部边。
+impl<N: TextPayload, E: TextPayload> fmt::Display for Graph<N, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let edge = |f: &mut fmt::Formatter<'_>, e: usize| {
+            write!(f, "%{e}")?;
+            let text = Payload(&self.edges[e]).to_string();
+            if text.is_empty() {
+                Ok(())
+            } else {
+                write!(f, ": {}", Quoted(&text))
+            }
+        };
+
+        for e in self.topology.global_inputs() {
+            edge(f, e.index())?;
+            writeln!(f, " = input")?;
+        }
+        let mut pass_edges = self.topology.global_inputs_len();
+        for (i, inputs, outputs) in &self.topology {
+            for e in pass_edges..outputs.start().index() {
+                edge(f, e)?;
+                writeln!(f, " = local")?;
+            }
+            pass_edges = outputs.end().index();
+
+            if !outputs.is_empty() {
+                for (j, e) in outputs.enumerate() {
+                    if j > 0 {
+                        write!(f, ", ")?;
+                    }
+                    edge(f, e.index())?;
+                }
+                write!(f, " = ")?;
+            }
+            write!(f, "node#{i}")?;
+            let text = Payload(&self.nodes[i.index()]).to_string();
+            if !text.is_empty() {
+                write!(f, " {}", Quoted(&text))?;
+            }
+            write!(f, "(")?;
+            write_uses(f, inputs)?;
+            writeln!(f, ")")?;
+        }
+        write!(f, "return")?;
+        if self.topology.global_outputs_len() > 0 {
+            write!(f, " ")?;
+            write_uses(f, self.topology.global_outputs())?;
+        }
+        writeln!(f)
+    }
+}
+
+fn write_uses(f: &mut fmt::Formatter<'_>, uses: &[OutputEdge]) -> fmt::Result {
+    for (j, e) in uses.iter().enumerate() {
+        if j > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "%{}", e.0)?;
+    }
+    Ok(())
+}
+
+/// 通过 [`TextPayload`] 格式化信息。
+struct Payload<'a, T>(&'a T);
+
+impl<T: TextPayload> fmt::Display for Payload<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_text(f)
+    }
+}
+
+/// 带引号和转义的字符串。
+struct Quoted<'a>(&'a str);
+
+impl fmt::Display for Quoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use fmt::Write;
+
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+/// 解析由 [`Display`](fmt::Display) 打印的文本。
+///
+/// 边的名字可以是任意标识符，按定义的顺序编号；节点的名字不会保留。
+/// 空行和 `//` 之后的注释将被忽略。
+impl<N: TextPayload, E: TextPayload> FromStr for Graph<N, E> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut topology = GraphTopo::default();
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut names = HashMap::<&str, EdgeIdx>::new();
+        let mut locals = 0usize;
+        let mut locals_line = 0;
+        let mut returned = false;
+
+        for (line, text) in s.lines().enumerate() {
+            let line = line + 1;
+            let syntax = ParseError::Syntax { line };
+            let tokens = lex(text).ok_or(syntax.clone())?;
+            let mut tokens = tokens.into_iter().peekable();
+            if tokens.peek().is_none() {
+                continue;
+            }
+            if returned {
+                return Err(ParseError::Misplaced { line });
+            }
+            let payload = |text: Option<String>| {
+                E::parse_text(text.as_deref().unwrap_or(""))
+                    .ok_or(ParseError::InvalidPayload { line })
+            };
+            let resolve = |names: &HashMap<&str, EdgeIdx>, name: &str| {
+                names
+                    .get(name)
+                    .map(|&e| OutputEdge(e))
+                    .ok_or_else(|| ParseError::UndefinedEdge {
+                        line,
+                        name: name.to_string(),
+                    })
+            };
+
+            // return %a, %b
+            if tokens.next_if_eq(&Token::Ident("return")).is_some() {
+                if locals > 0 {
+                    return Err(ParseError::Misplaced { line });
+                }
+                let outputs = uses(&mut tokens, None).ok_or(syntax.clone())?;
+                for name in &outputs {
+                    topology.connections.push(resolve(&names, name)?);
+                }
+                topology.global_outputs_len = outputs.len();
+                returned = true;
+                continue;
+            }
+
+            // %a: "payload", %b = ...
+            let mut defs = Vec::new();
+            if matches!(tokens.peek(), Some(Token::Edge(_))) {
+                loop {
+                    let Some(Token::Edge(name)) = tokens.next() else {
+                        return Err(syntax);
+                    };
+                    let info = match tokens.next_if(|t| *t == Token::Punct(':')) {
+                        Some(_) => match tokens.next() {
+                            Some(Token::Str(text)) => Some(text),
+                            _ => return Err(syntax),
+                        },
+                        None => None,
+                    };
+                    defs.push((name, payload(info)?));
+                    match tokens.next() {
+                        Some(Token::Punct(',')) => continue,
+                        Some(Token::Punct('=')) => break,
+                        _ => return Err(syntax),
+                    }
+                }
+            }
+            let Some(Token::Ident(op)) = tokens.next() else {
+                return Err(syntax);
+            };
+            match op {
+                "input" | "local" => {
+                    if defs.is_empty() || tokens.next().is_some() {
+                        return Err(syntax);
+                    }
+                    if op == "input" {
+                        if !topology.nodes.is_empty() || locals > 0 {
+                            return Err(ParseError::Misplaced { line });
+                        }
+                        topology.global_inputs_len += defs.len();
+                    } else {
+                        locals += defs.len();
+                        locals_line = line;
+                    }
+                }
+                _ => {
+                    let info = match tokens.next_if(|t| matches!(t, Token::Str(_))) {
+                        Some(Token::Str(text)) => text,
+                        _ => String::new(),
+                    };
+                    nodes.push(N::parse_text(&info).ok_or(ParseError::InvalidPayload { line })?);
+                    let inputs = uses(&mut tokens, Some(('(', ')'))).ok_or(syntax)?;
+                    for name in &inputs {
+                        topology.connections.push(resolve(&names, name)?);
+                    }
+                    let node = (
+                        try_repr(locals),
+                        try_repr(inputs.len()),
+                        try_repr(defs.len()),
+                    );
+                    let (Some(local_edges_len), Some(inputs_len), Some(outputs_len)) = node else {
+                        return Err(ParseError::Overflow);
+                    };
+                    topology.nodes.push(Node {
+                        local_edges_len,
+                        inputs_len,
+                        outputs_len,
+                    });
+                    locals = 0;
+                }
+            }
+            for (name, info) in defs {
+                let e = EdgeIdx::try_new(edges.len()).ok_or(ParseError::Overflow)?;
+                if names.insert(name, e).is_some() {
+                    return Err(ParseError::DuplicateEdge {
+                        line,
+                        name: name.to_string(),
+                    });
+                }
+                edges.push(info);
+            }
+        }
+        if locals > 0 {
+            return Err(ParseError::Misplaced { line: locals_line });
+        }
+
+        let graph = Graph {
+            topology,
+            nodes,
+            edges,
+            names: None,
+        };
+        debug_assert_eq!(graph.validate(), Ok(()));
+        Ok(graph)
+    }
+}
+
+/// 词法单元。
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Token<'a> {
+    /// `%` 开头的边名字。
+    Edge(&'a str),
+    /// 标识符。
+    Ident(&'a str),
+    /// 已去除转义的字符串。
+    Str(String),
+    /// 标点符号。
+    Punct(char),
+}
+
+/// 将一行文本切分为词法单元，遇到无法识别的字符时返回 `None`。
+fn lex(text: &str) -> Option<Vec<Token<'_>>> {
+    let is_ident = |c: char| c.is_alphanumeric() || matches!(c, '_' | '#' | '.' | '-');
+    let mut ans = Vec::new();
+    let mut rest = text;
+    loop {
+        rest = rest.trim_start();
+        let mut chars = rest.chars();
+        let Some(c) = chars.next() else {
+            break;
+        };
+        match c {
+            '/' if rest.starts_with("//") => break,
+            ',' | '=' | ':' | '(' | ')' => {
+                ans.push(Token::Punct(c));
+                rest = chars.as_str();
+            }
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => s.push(match chars.next()? {
+                            'n' => '\n',
+                            c @ ('"' | '\\') => c,
+                            _ => return None,
+                        }),
+                        c => s.push(c),
+                    }
+                }
+                ans.push(Token::Str(s));
+                rest = chars.as_str();
+            }
+            _ => {
+                let body = if c == '%' { chars.as_str() } else { rest };
+                let len = body.find(|c| !is_ident(c)).unwrap_or(body.len());
+                if len == 0 {
+                    return None;
+                }
+                let (name, tail) = body.split_at(len);
+                ans.push(if c == '%' {
+                    Token::Edge(name)
+                } else {
+                    Token::Ident(name)
+                });
+                rest = tail;
+            }
+        }
+    }
+    Some(ans)
+}
+
+/// 解析以逗号分隔的边列表，直到行尾，`brackets` 指定列表外的括号。
+fn uses<'a>(
+    tokens: &mut impl Iterator<Item = Token<'a>>,
+    brackets: Option<(char, char)>,
+) -> Option<Vec<&'a str>> {
+    let mut tokens = tokens.peekable();
+    if let Some((open, _)) = brackets {
+        tokens.next_if_eq(&Token::Punct(open))?;
+    }
+    let close = brackets.map(|(_, close)| Token::Punct(close));
+    let mut ans = Vec::new();
+    loop {
+        match tokens.next() {
+            Some(Token::Edge(name)) => ans.push(name),
+            t if t == close && ans.is_empty() => break,
+            _ => return None,
+        }
+        match tokens.next() {
+            Some(Token::Punct(',')) => continue,
+            t if t == close => break,
+            _ => return None,
+        }
+    }
+    tokens.next().is_none().then_some(ans)
+}
+
+#[test]
+fn test_text() {
+    use crate::Builder;
+
+    let text = "
+        // 全图输入
+        %x: \"f32\" = input
+        %w = local
+        %y, %z: \"unused\" = node#0 \"Split\"(%x, %w)
+        node#1 \"Print\"(%y)
+        %o = node#2(%y, %y)
+        return %o, %x
+    ";
+    let graph = text.parse::<Graph<String, String>>().unwrap();
+
+    let mut builder = Builder::default();
+    builder.add_global_input("x").unwrap();
+    builder
+        .add_node(0, vec!["x", "w"], vec!["y", "z"], "Split".to_string())
+        .unwrap();
+    builder
+        .add_node(1, vec!["y"], vec![], "Print".to_string())
+        .unwrap();
+    builder
+        .add_node(2, vec!["y", "y"], vec!["o"], String::new())
+        .unwrap();
+    builder.mark_global_output("o");
+    builder.mark_global_output("x");
+    builder.set_edge_info("x", "f32".to_string());
+    builder.set_edge_info("w", String::new());
+    builder.set_edge_info("z", "unused".to_string());
+    let expected = builder.build().unwrap();
+    assert_eq!(graph.topology, expected.topology);
+    assert_eq!(graph.nodes, expected.nodes);
+    assert_eq!(graph.edges, expected.edges);
+
+    let printed = graph.to_string();
+    assert_eq!(
+        printed,
+        "\
+%0: \"f32\" = input
+%1 = local
+%2, %3: \"unused\" = node#0 \"Split\"(%0, %1)
+node#1 \"Print\"(%2)
+%4 = node#2(%2, %2)
+return %4, %0
+"
+    );
+    let reparsed = printed.parse::<Graph<String, String>>().unwrap();
+    assert_eq!(reparsed.topology, graph.topology);
+    assert_eq!(reparsed.edges, graph.edges);
+
+    assert_eq!(
+        "%a = node#0(%b)".parse::<Graph<(), ()>>().unwrap_err(),
+        ParseError::UndefinedEdge {
+            line: 1,
+            name: "b".to_string()
+        }
+    );
+    assert_eq!(
+        "%a = input\n%a = node#0(%a)"
+            .parse::<Graph<(), ()>>()
+            .unwrap_err(),
+        ParseError::DuplicateEdge {
+            line: 2,
+            name: "a".to_string()
+        }
+    );
+    assert_eq!(
+        "%a = local".parse::<Graph<(), ()>>().unwrap_err(),
+        ParseError::Misplaced { line: 1 }
+    );
+    assert_eq!(
+        "%a: \"x\" = input".parse::<Graph<(), ()>>().unwrap_err(),
+        ParseError::InvalidPayload { line: 1 }
+    );
+}
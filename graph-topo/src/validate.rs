@@ -0,0 +1,222 @@
+use crate::{idx::try_repr, EdgeIdx, Graph, GraphTopo, NodeIdx};
+use std::fmt;
+
+/// 图拓扑不满足不变量。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TopoError {
+    /// 边、节点或连接的数量超出了可表示的范围。
+    Overflow,
+    /// 连接数量不等于所有节点入边数量与全图输出边数量之和。
+    ConnectionsLen {
+        /// 由节点和全图输出计算出的连接数量。
+        expected: usize,
+        /// 实际的连接数量。
+        actual: usize,
+    },
+    /// 节点的入边引用了在这个节点之前未定义的边。
+    UndefinedInput {
+        /// 节点序号。
+        node: NodeIdx,
+        /// 入边的位置。
+        slot: usize,
+        /// 引用的边。
+        edge: EdgeIdx,
+    },
+    /// 全图输出引用了不存在的边。
+    UndefinedOutput {
+        /// 全图输出的位置。
+        slot: usize,
+        /// 引用的边。
+        edge: EdgeIdx,
+    },
+    /// 节点信息的数量与拓扑中的节点数量不一致。
+    NodesLen {
+        /// 拓扑中的节点数量。
+        expected: usize,
+        /// 节点信息的数量。
+        actual: usize,
+    },
+    /// 边信息的数量与拓扑中的边数量不一致。
+    EdgesLen {
+        /// 拓扑中的边数量。
+        expected: usize,
+        /// 边信息的数量。
+        actual: usize,
+    },
+    /// 名字表的长度与节点或边的数量不一致。
+    NamesLen,
+}
+
+impl fmt::Display for TopoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Overflow => write!(f, "edge or connection count overflows"),
+            Self::ConnectionsLen { expected, actual } => {
+                write!(f, "expected {expected} connections, found {actual}")
+            }
+            Self::UndefinedInput { node, slot, edge } => {
+                write!(
+                    f,
+                    "input {slot} of node {node} refers to undefined edge {edge}"
+                )
+            }
+            Self::UndefinedOutput { slot, edge } => {
+                write!(f, "global output {slot} refers to undefined edge {edge}")
+            }
+            Self::NodesLen { expected, actual } => {
+                write!(f, "expected {expected} nodes, found {actual}")
+            }
+            Self::EdgesLen { expected, actual } => {
+                write!(f, "expected {expected} edges, found {actual}")
+            }
+            Self::NamesLen => write!(f, "names do not match nodes or edges"),
+        }
+    }
+}
+
+impl std::error::Error for TopoError {}
+
+impl GraphTopo {
+    /// 检查图拓扑的所有不变量：
+    ///
+    /// - `connections.len() == nodes.sum(inputs_len) + global_outputs_len`；
+    /// - 节点的入边只能引用全图输入边、之前节点的边和这个节点的局部边；
+    /// - 全图输出只能引用存在的边；
+    /// - 边和节点的数量不超过序号存储类型的范围。
+    pub fn validate(&self) -> Result<(), TopoError> {
+        let mut connections_len = 0usize;
+        let mut edges_len = self.global_inputs_len;
+        for node in &self.nodes {
+            connections_len = connections_len
+                .checked_add(node.inputs_len())
+                .ok_or(TopoError::Overflow)?;
+            edges_len = edges_len
+                .checked_add(node.local_edges_len())
+                .and_then(|n| n.checked_add(node.outputs_len()))
+                .ok_or(TopoError::Overflow)?;
+        }
+        // 之后的检查会构造边和节点序号，必须先保证它们都能用存储类型表示
+        if try_repr(edges_len).is_none() || try_repr(self.nodes.len()).is_none() {
+            return Err(TopoError::Overflow);
+        }
+        let expected = connections_len
+            .checked_add(self.global_outputs_len)
+            .ok_or(TopoError::Overflow)?;
+        if self.connections.len() != expected {
+            return Err(TopoError::ConnectionsLen {
+                expected,
+                actual: self.connections.len(),
+            });
+        }
+
+        for (node, inputs, outputs) in self {
+            for (slot, input) in inputs.iter().enumerate() {
+                if input.0 >= outputs.start() {
+                    return Err(TopoError::UndefinedInput {
+                        node,
+                        slot,
+                        edge: input.0,
+                    });
+                }
+            }
+        }
+        for (slot, output) in self.global_outputs().iter().enumerate() {
+            if output.0.index() >= edges_len {
+                return Err(TopoError::UndefinedOutput {
+                    slot,
+                    edge: output.0,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<N, E> Graph<N, E> {
+    /// 检查图拓扑的所有不变量，以及节点信息、边信息和名字表与拓扑一致。
+    pub fn validate(&self) -> Result<(), TopoError> {
+        self.topology.validate()?;
+        let nodes_len = self.topology.nodes_len();
+        let edges_len = self.topology.calculate_edge_len();
+        if self.nodes.len() != nodes_len {
+            return Err(TopoError::NodesLen {
+                expected: nodes_len,
+                actual: self.nodes.len(),
+            });
+        }
+        if self.edges.len() != edges_len {
+            return Err(TopoError::EdgesLen {
+                expected: edges_len,
+                actual: self.edges.len(),
+            });
+        }
+        match &self.names {
+            Some(names) if names.nodes.len() != nodes_len || names.edges.len() != edges_len => {
+                Err(TopoError::NamesLen)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn test_validate() {
+    use crate::{container::Node, OutputEdge};
+
+    let mut topo = GraphTopo {
+        global_inputs_len: 1,
+        global_outputs_len: 1,
+        nodes: vec![Node::new(1, 2, 1), Node::new(0, 1, 1)],
+        connections: [0, 1, 2, 3]
+            .into_iter()
+            .map(|i| OutputEdge(EdgeIdx::new(i)))
+            .collect(),
+        offsets: Default::default(),
+    };
+    assert_eq!(topo.validate(), Ok(()));
+
+    topo.connections[2] = OutputEdge(EdgeIdx::new(3));
+    assert_eq!(
+        topo.validate(),
+        Err(TopoError::UndefinedInput {
+            node: NodeIdx::new(1),
+            slot: 0,
+            edge: EdgeIdx::new(3),
+        })
+    );
+
+    topo.connections[2] = OutputEdge(EdgeIdx::new(2));
+    topo.connections[3] = OutputEdge(EdgeIdx::new(4));
+    assert_eq!(
+        topo.validate(),
+        Err(TopoError::UndefinedOutput {
+            slot: 0,
+            edge: EdgeIdx::new(4),
+        })
+    );
+
+    topo.connections.pop();
+    assert_eq!(
+        topo.validate(),
+        Err(TopoError::ConnectionsLen {
+            expected: 4,
+            actual: 3,
+        })
+    );
+}
+
+#[cfg(all(feature = "compact-index", target_pointer_width = "64"))]
+#[test]
+fn test_validate_overflow() {
+    use crate::container::Node;
+
+    // 边的总数超出 u32，即使节点没有入边也不能通过检查
+    let topo = GraphTopo {
+        global_inputs_len: 5_000_000_000,
+        global_outputs_len: 0,
+        nodes: vec![Node::new(0, 0, 1)],
+        connections: Vec::new(),
+        offsets: Default::default(),
+    };
+    assert_eq!(topo.validate(), Err(TopoError::Overflow));
+}